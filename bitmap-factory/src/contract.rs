@@ -0,0 +1,328 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+};
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::state::{Config, CONFIG, NEXT_ID};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:bitmap-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Reply id of the canvas WasmMsg::Instantiate submessage
+pub const INSTANTIATE_REPLY_ID: u64 = 1;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let admin = match msg.admin {
+        Some(ref admin) => deps.api.addr_validate(admin)?,
+        None => info.sender.clone(),
+    };
+    let fee_recipient = match msg.fee_recipient {
+        Some(ref recipient) => deps.api.addr_validate(recipient)?,
+        None => admin.clone(),
+    };
+    let config = Config {
+        admin,
+        pay_code_id: msg.pay_code_id,
+        free_code_id: msg.free_code_id,
+        creation_fee: msg.creation_fee.filter(|fee| !fee.amount.is_zero()),
+        fee_recipient,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    NEXT_ID.save(deps.storage, &0)?;
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", config.admin)
+        .add_attribute("pay_code_id", config.pay_code_id.to_string())
+        .add_attribute("free_code_id", config.free_code_id.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateCanvas {
+            canvas,
+            label,
+            metadata,
+        } => execute::create_canvas(deps, env, info, canvas, label, metadata),
+        ExecuteMsg::UpdateConfig {
+            pay_code_id,
+            free_code_id,
+            creation_fee,
+            fee_recipient,
+            admin,
+        } => execute::update_config(
+            deps,
+            info,
+            pay_code_id,
+            free_code_id,
+            creation_fee,
+            fee_recipient,
+            admin,
+        ),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_REPLY_ID => execute::register_canvas(deps, env, msg),
+        id => Err(ContractError::UnknownReply { id }),
+    }
+}
+
+pub mod execute {
+    use super::*;
+
+    use cosmwasm_std::{BankMsg, Coin, Empty, SubMsg, WasmMsg};
+
+    use crate::msg::{CanvasInfo, CanvasInit, CanvasKind, CanvasMetadata};
+    use crate::state::{PendingCanvas, CANVASES, CREATOR_CANVASES, PENDING};
+
+    pub const MAX_NAME_LEN: usize = 64;
+    pub const MAX_DESCRIPTION_LEN: usize = 512;
+
+    fn validate_metadata(metadata: &CanvasMetadata) -> Result<(), ContractError> {
+        let reason = if metadata.name.trim().is_empty() {
+            "name is empty".to_string()
+        } else if metadata.name.chars().count() > MAX_NAME_LEN {
+            format!("name is longer than {MAX_NAME_LEN} characters")
+        } else if metadata
+            .description
+            .as_ref()
+            .is_some_and(|description| description.chars().count() > MAX_DESCRIPTION_LEN)
+        {
+            format!("description is longer than {MAX_DESCRIPTION_LEN} characters")
+        } else {
+            return Ok(());
+        };
+        Err(ContractError::InvalidMetadata { reason })
+    }
+
+    pub fn create_canvas(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        canvas: CanvasInit,
+        label: String,
+        metadata: CanvasMetadata,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        validate_metadata(&metadata)?;
+
+        let mut res = Response::new().add_attribute("method", "create_canvas");
+        match config.creation_fee {
+            Some(ref fee) => {
+                if info.funds != [fee.clone()] {
+                    return Err(ContractError::InvalidFunds {});
+                }
+                res = res.add_message(BankMsg::Send {
+                    to_address: config.fee_recipient.to_string(),
+                    amount: vec![fee.clone()],
+                });
+            }
+            None => {
+                if !info.funds.is_empty() {
+                    return Err(ContractError::InvalidFunds {});
+                }
+            }
+        }
+
+        // The canvas admin defaults to the creator rather than the factory. Chunked
+        // init is refused since the factory would be the only allowed uploader.
+        let creator = info.sender.to_string();
+        let (kind, code_id, msg) = match canvas {
+            CanvasInit::Pay(mut msg) => {
+                if msg.chunked_init.unwrap_or(false) {
+                    return Err(ContractError::ChunkedInitUnsupported {});
+                }
+                msg.admin.get_or_insert_with(|| creator.clone());
+                (CanvasKind::Pay, config.pay_code_id, to_json_binary(&msg)?)
+            }
+            CanvasInit::Free(mut msg) => {
+                if msg.chunked_init.unwrap_or(false) {
+                    return Err(ContractError::ChunkedInitUnsupported {});
+                }
+                msg.admin.get_or_insert_with(|| creator.clone());
+                (CanvasKind::Free, config.free_code_id, to_json_binary(&msg)?)
+            }
+        };
+
+        PENDING.save(
+            deps.storage,
+            &PendingCanvas {
+                kind,
+                code_id,
+                creator: info.sender.clone(),
+                metadata,
+            },
+        )?;
+        let instantiate = WasmMsg::Instantiate {
+            admin: Some(creator),
+            code_id,
+            msg,
+            funds: vec![],
+            label,
+        };
+        Ok(res
+            .add_attribute("code_id", code_id.to_string())
+            .add_submessage(SubMsg::reply_on_success(instantiate, INSTANTIATE_REPLY_ID)))
+    }
+
+    pub fn register_canvas(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+        let pending = PENDING.load(deps.storage)?;
+        PENDING.remove(deps.storage);
+        let response = msg
+            .result
+            .into_result()
+            .map_err(|_| ContractError::MissingContractAddress {})?;
+        let address = response
+            .events
+            .iter()
+            .filter(|event| event.ty == "instantiate")
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == "_contract_address")
+            .map(|attr| deps.api.addr_validate(&attr.value))
+            .transpose()?
+            .ok_or(ContractError::MissingContractAddress {})?;
+
+        let id = NEXT_ID.load(deps.storage)?;
+        NEXT_ID.save(deps.storage, &(id + 1))?;
+        let info = CanvasInfo {
+            id,
+            address: address.clone(),
+            kind: pending.kind,
+            code_id: pending.code_id,
+            creator: pending.creator,
+            metadata: pending.metadata,
+            created_at: env.block.height,
+        };
+        CANVASES.save(deps.storage, id, &info)?;
+        CREATOR_CANVASES.save(deps.storage, (&info.creator, id), &Empty {})?;
+        Ok(Response::new()
+            .add_attribute("method", "register_canvas")
+            .add_attribute("canvas_id", id.to_string())
+            .add_attribute("address", address)
+            .add_attribute("creator", info.creator))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_config(
+        deps: DepsMut,
+        info: MessageInfo,
+        pay_code_id: Option<u64>,
+        free_code_id: Option<u64>,
+        creation_fee: Option<Coin>,
+        fee_recipient: Option<String>,
+        admin: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
+        if let Some(code_id) = pay_code_id {
+            config.pay_code_id = code_id;
+        }
+        if let Some(code_id) = free_code_id {
+            config.free_code_id = code_id;
+        }
+        if let Some(fee) = creation_fee {
+            config.creation_fee = Some(fee).filter(|fee| !fee.amount.is_zero());
+        }
+        if let Some(recipient) = fee_recipient {
+            config.fee_recipient = deps.api.addr_validate(&recipient)?;
+        }
+        if let Some(admin) = admin {
+            config.admin = deps.api.addr_validate(&admin)?;
+        }
+        CONFIG.save(deps.storage, &config)?;
+        Ok(Response::new().add_attribute("method", "update_config"))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_json_binary(&query::config(deps)?),
+        QueryMsg::GetCanvas { id } => to_json_binary(&query::canvas(deps, id)?),
+        QueryMsg::ListCanvases {
+            creator,
+            start_after,
+            limit,
+        } => to_json_binary(&query::list_canvases(deps, creator, start_after, limit)?),
+    }
+}
+
+pub mod query {
+    use super::*;
+
+    use cosmwasm_std::Order;
+    use cw_storage_plus::Bound;
+
+    use crate::msg::{CanvasInfo, ConfigResponse, ListCanvasesResponse};
+    use crate::state::{CANVASES, CREATOR_CANVASES};
+
+    pub const DEFAULT_CANVASES_LIMIT: u32 = 30;
+    pub const MAX_CANVASES_LIMIT: u32 = 100;
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        Ok(ConfigResponse {
+            admin: config.admin,
+            pay_code_id: config.pay_code_id,
+            free_code_id: config.free_code_id,
+            creation_fee: config.creation_fee,
+            fee_recipient: config.fee_recipient,
+            num_canvases: NEXT_ID.load(deps.storage)?,
+        })
+    }
+
+    pub fn canvas(deps: Deps, id: u64) -> StdResult<CanvasInfo> {
+        CANVASES.load(deps.storage, id)
+    }
+
+    pub fn list_canvases(
+        deps: Deps,
+        creator: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ListCanvasesResponse> {
+        let limit = limit
+            .unwrap_or(DEFAULT_CANVASES_LIMIT)
+            .min(MAX_CANVASES_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+        let canvases = match creator {
+            Some(creator) => {
+                let creator = deps.api.addr_validate(&creator)?;
+                CREATOR_CANVASES
+                    .prefix(&creator)
+                    .keys(deps.storage, start, None, Order::Ascending)
+                    .take(limit)
+                    .map(|id| CANVASES.load(deps.storage, id?))
+                    .collect::<StdResult<Vec<_>>>()?
+            }
+            None => CANVASES
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(_, info)| info))
+                .collect::<StdResult<Vec<_>>>()?,
+        };
+        Ok(ListCanvasesResponse { canvases })
+    }
+}
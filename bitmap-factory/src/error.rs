@@ -0,0 +1,21 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+    #[error("Funds must be exactly the creation fee")]
+    InvalidFunds {},
+    #[error("Invalid metadata: {reason}")]
+    InvalidMetadata { reason: String },
+    #[error("Chunked init is not supported through the factory, instantiate the canvas directly")]
+    ChunkedInitUnsupported {},
+    #[error("Instantiate reply is missing the contract address")]
+    MissingContractAddress {},
+    #[error("Unknown reply id {id}")]
+    UnknownReply { id: u64 },
+}
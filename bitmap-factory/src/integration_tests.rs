@@ -0,0 +1,331 @@
+#[cfg(test)]
+mod tests {
+    use crate::helpers::CwTemplateContract;
+    use crate::msg::{
+        CanvasInfo, CanvasInit, CanvasKind, CanvasMetadata, ConfigResponse, ExecuteMsg,
+        InstantiateMsg, ListCanvasesResponse, QueryMsg,
+    };
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::{coin, Addr, Coin, Empty, Uint128};
+    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+
+    pub fn contract_template() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        )
+        .with_reply(crate::contract::reply);
+        Box::new(contract)
+    }
+
+    pub fn contract_pay() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            bitmap_pay::contract::execute,
+            bitmap_pay::contract::instantiate,
+            bitmap_pay::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    pub fn contract_free() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            bitmap_free::contract::execute,
+            bitmap_free::contract::instantiate,
+            bitmap_free::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    const USER: &str = "USER";
+    const ADMIN: &str = "ADMIN";
+    const RECIPIENT: &str = "RECIPIENT";
+    const NATIVE_DENOM: &str = "denom";
+
+    fn mock_app() -> App {
+        AppBuilder::new().build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &MockApi::default().addr_make(USER),
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    }],
+                )
+                .unwrap();
+        })
+    }
+
+    fn proper_instantiate(creation_fee: Option<Coin>) -> (App, CwTemplateContract) {
+        let mut app = mock_app();
+        let pay_code_id = app.store_code(contract_pay());
+        let free_code_id = app.store_code(contract_free());
+        let cw_template_id = app.store_code(contract_template());
+        let msg = InstantiateMsg {
+            pay_code_id,
+            free_code_id,
+            admin: None,
+            creation_fee,
+            fee_recipient: Some(MockApi::default().addr_make(RECIPIENT).to_string()),
+        };
+        let cw_template_contract_addr = app
+            .instantiate_contract(
+                cw_template_id,
+                Addr::unchecked(ADMIN),
+                &msg,
+                &[],
+                "test",
+                None,
+            )
+            .unwrap();
+        let cw_template_contract = CwTemplateContract(cw_template_contract_addr);
+        (app, cw_template_contract)
+    }
+
+    fn pay_canvas() -> CanvasInit {
+        CanvasInit::Pay(Box::new(bitmap_pay::msg::InstantiateMsg {
+            x_size: 2,
+            y_size: 2,
+            z_values: None,
+            recipient: ADMIN.to_string(),
+            supply_base_fee: 100,
+            supply_fee_factor: 10,
+            update_base_fee: 100,
+            update_fee_factor: 10,
+            fee_factor_scale: 100,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: None,
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        }))
+    }
+
+    fn free_canvas(chunked_init: Option<bool>) -> CanvasInit {
+        CanvasInit::Free(Box::new(bitmap_free::msg::InstantiateMsg {
+            x_size: 2,
+            y_size: 2,
+            z_values: None,
+            admin: None,
+            moderators: None,
+            rate_limit: None,
+            access_policy: None,
+            chunked_init,
+            palette: None,
+            bit_depth: None,
+        }))
+    }
+
+    fn metadata(name: &str) -> CanvasMetadata {
+        CanvasMetadata {
+            name: name.to_string(),
+            description: None,
+        }
+    }
+
+    fn create_msg(canvas: CanvasInit, name: &str) -> ExecuteMsg {
+        ExecuteMsg::CreateCanvas {
+            canvas,
+            label: name.to_string(),
+            metadata: metadata(name),
+        }
+    }
+
+    #[test]
+    fn test_create_canvases() {
+        let fee = coin(1_000, NATIVE_DENOM);
+        let (mut app, cw_template_contract) = proper_instantiate(Some(fee.clone()));
+        let user = MockApi::default().addr_make(USER);
+
+        // Funds must be exactly the creation fee
+        for funds in [vec![], vec![coin(999, NATIVE_DENOM)]] {
+            let err = app
+                .execute_contract(
+                    user.clone(),
+                    cw_template_contract.addr(),
+                    &create_msg(pay_canvas(), "pay"),
+                    &funds,
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.root_cause().to_string(),
+                "Funds must be exactly the creation fee"
+            );
+        }
+        let err = app
+            .execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &create_msg(pay_canvas(), " "),
+                std::slice::from_ref(&fee),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Invalid metadata: name is empty"
+        );
+        let err = app
+            .execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &create_msg(free_canvas(Some(true)), "chunked"),
+                std::slice::from_ref(&fee),
+            )
+            .unwrap_err();
+        assert!(err
+            .root_cause()
+            .to_string()
+            .starts_with("Chunked init is not supported"));
+
+        // The reply registers each canvas with its address
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &create_msg(pay_canvas(), "pay"),
+            std::slice::from_ref(&fee),
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cw_template_contract.addr(),
+            &ExecuteMsg::UpdateConfig {
+                pay_code_id: None,
+                free_code_id: None,
+                creation_fee: Some(coin(0, NATIVE_DENOM)),
+                fee_recipient: None,
+                admin: None,
+            },
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &create_msg(free_canvas(None), "free"),
+            &[],
+        )
+        .unwrap();
+
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(config.num_canvases, 2);
+        assert_eq!(config.creation_fee, None);
+        let recipient = MockApi::default().addr_make(RECIPIENT);
+        let recipient_balance = app.wrap().query_balance(&recipient, NATIVE_DENOM).unwrap();
+        assert_eq!(recipient_balance.amount, fee.amount);
+
+        let list: ListCanvasesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::ListCanvases {
+                    creator: Some(user.to_string()),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(list.canvases.len(), 2);
+        let pay: CanvasInfo = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCanvas { id: 0 })
+            .unwrap();
+        assert_eq!(pay, list.canvases[0]);
+        assert_eq!(pay.kind, CanvasKind::Pay);
+        assert_eq!(pay.creator, user);
+        assert_eq!(list.canvases[1].kind, CanvasKind::Free);
+        let list: ListCanvasesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::ListCanvases {
+                    creator: Some(recipient.to_string()),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert!(list.canvases.is_empty());
+
+        // The created canvases answer queries and belong to the creator
+        let grid: bitmap_pay::msg::GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(
+                pay.address.clone(),
+                &bitmap_pay::msg::QueryMsg::GetGrid {
+                    canvas_id: None,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!((grid.x_size, grid.y_size), (2, 2));
+        let canvases: bitmap_pay::msg::ListCanvasesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                pay.address.clone(),
+                &bitmap_pay::msg::QueryMsg::ListCanvases {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(canvases.canvases[0].admin, user);
+        assert_eq!(
+            app.contract_data(&pay.address).unwrap().admin,
+            Some(user.clone())
+        );
+        let moderators: bitmap_free::msg::GetModeratorsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                list_address(&app, &cw_template_contract, 1),
+                &bitmap_free::msg::QueryMsg::GetModerators {},
+            )
+            .unwrap();
+        assert_eq!(moderators.admin, user);
+    }
+
+    fn list_address(app: &App, contract: &CwTemplateContract, id: u64) -> Addr {
+        let info: CanvasInfo = app
+            .wrap()
+            .query_wasm_smart(contract.addr(), &QueryMsg::GetCanvas { id })
+            .unwrap();
+        info.address
+    }
+
+    #[test]
+    fn test_update_config() {
+        let (mut app, cw_template_contract) = proper_instantiate(None);
+        let user = MockApi::default().addr_make(USER);
+        let update = ExecuteMsg::UpdateConfig {
+            pay_code_id: Some(42),
+            free_code_id: None,
+            creation_fee: Some(coin(5, NATIVE_DENOM)),
+            fee_recipient: Some(user.to_string()),
+            admin: None,
+        };
+        let err = app
+            .execute_contract(user.clone(), cw_template_contract.addr(), &update, &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cw_template_contract.addr(),
+            &update,
+            &[],
+        )
+        .unwrap();
+        let config: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetConfig {})
+            .unwrap();
+        assert_eq!(config.pay_code_id, 42);
+        assert_eq!(config.creation_fee, Some(coin(5, NATIVE_DENOM)));
+        assert_eq!(config.fee_recipient, user);
+        assert_eq!(config.num_canvases, 0);
+    }
+}
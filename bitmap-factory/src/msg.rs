@@ -0,0 +1,105 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Coin};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub pay_code_id: u64,
+    pub free_code_id: u64,
+    /// Defaults to the instantiating address
+    pub admin: Option<String>,
+    /// Charged on every CreateCanvas if set
+    pub creation_fee: Option<Coin>,
+    /// Receives the creation fees, defaults to the admin
+    pub fee_recipient: Option<String>,
+}
+
+/// Instantiate message of the canvas to create
+#[cw_serde]
+pub enum CanvasInit {
+    Pay(Box<bitmap_pay::msg::InstantiateMsg>),
+    Free(Box<bitmap_free::msg::InstantiateMsg>),
+}
+
+#[cw_serde]
+pub enum CanvasKind {
+    Pay,
+    Free,
+}
+
+#[cw_serde]
+pub struct CanvasMetadata {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// CreateCanvas instantiates a bitmap-pay or bitmap-free canvas and adds it to
+    /// the registry. The sender becomes the canvas admin, unless the message sets
+    /// one, and the wasm admin of the new contract. Funds must be exactly the
+    /// creation fee, or empty if there is none.
+    CreateCanvas {
+        canvas: CanvasInit,
+        label: String,
+        metadata: CanvasMetadata,
+    },
+
+    /// UpdateConfig changes the given settings (admin only). A creation fee with
+    /// a zero amount removes the fee.
+    UpdateConfig {
+        pay_code_id: Option<u64>,
+        free_code_id: Option<u64>,
+        creation_fee: Option<Coin>,
+        fee_recipient: Option<String>,
+        admin: Option<String>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// GetConfig returns the code IDs, admin and creation fee
+    #[returns(ConfigResponse)]
+    GetConfig {},
+
+    /// GetCanvas returns a created canvas by id
+    #[returns(CanvasInfo)]
+    GetCanvas { id: u64 },
+
+    /// ListCanvases returns the created canvases in id order, optionally only
+    /// those of one creator
+    #[returns(ListCanvasesResponse)]
+    ListCanvases {
+        creator: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub admin: Addr,
+    pub pay_code_id: u64,
+    pub free_code_id: u64,
+    pub creation_fee: Option<Coin>,
+    pub fee_recipient: Addr,
+    /// Number of canvases created so far
+    pub num_canvases: u64,
+}
+
+#[cw_serde]
+pub struct CanvasInfo {
+    pub id: u64,
+    pub address: Addr,
+    pub kind: CanvasKind,
+    pub code_id: u64,
+    pub creator: Addr,
+    pub metadata: CanvasMetadata,
+    /// Block height of the CreateCanvas
+    pub created_at: u64,
+}
+
+#[cw_serde]
+pub struct ListCanvasesResponse {
+    pub canvases: Vec<CanvasInfo>,
+}
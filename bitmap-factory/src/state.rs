@@ -0,0 +1,36 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::msg::{CanvasInfo, CanvasKind, CanvasMetadata};
+
+use cosmwasm_std::{Addr, Coin, Empty};
+use cw_storage_plus::{Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub admin: Addr,
+    pub pay_code_id: u64,
+    pub free_code_id: u64,
+    pub creation_fee: Option<Coin>, // None if creating canvases is free
+    pub fee_recipient: Addr,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingCanvas {
+    pub kind: CanvasKind,
+    pub code_id: u64,
+    pub creator: Addr,
+    pub metadata: CanvasMetadata,
+}
+
+// Canvas being instantiated, saved by CreateCanvas and consumed by its reply
+pub const PENDING: Item<PendingCanvas> = Item::new("pending");
+
+// Registry of created canvases, keyed by id
+pub const CANVASES: Map<u64, CanvasInfo> = Map::new("canvases");
+// Id of the next created canvas
+pub const NEXT_ID: Item<u64> = Item::new("next_id");
+// Canvas ids of each creator
+pub const CREATOR_CANVASES: Map<(&Addr, u64), Empty> = Map::new("creator_canvases");
@@ -0,0 +1,50 @@
+use crate::error::ContractError;
+use crate::grid;
+use crate::msg::BlendMode;
+
+/// Number of characters of an RGBA paint value
+pub const RGBA_LEN: usize = 8;
+
+/// Parses the z of a Set: 6 character RGB, which is fully opaque, or 8 character RGBA
+pub fn parse_paint(z: &str) -> Option<[u8; 4]> {
+    if !z.is_ascii() {
+        return None;
+    }
+    match z.len() {
+        grid::Z_LEN => {
+            let [r, g, b] = grid::parse_color(z)?;
+            Some([r, g, b, u8::MAX])
+        }
+        RGBA_LEN => {
+            let [r, g, b] = grid::parse_color(&z[..grid::Z_LEN])?;
+            let a = u8::from_str_radix(&z[grid::Z_LEN..], 16).ok()?;
+            Some([r, g, b, a])
+        }
+        _ => None,
+    }
+}
+
+/// Weighs src over dst by alpha, rounding to the nearest channel value
+fn mix(dst: [u8; 3], src: [u8; 3], alpha: u8) -> [u8; 3] {
+    let (a, inv) = (alpha as u32, (u8::MAX - alpha) as u32);
+    let channel = |i: usize| ((src[i] as u32 * a + dst[i] as u32 * inv + 127) / 255) as u8;
+    [channel(0), channel(1), channel(2)]
+}
+
+/// Returns the value stored for a Set of z on a point holding `existing`.
+/// Replace keeps a 6 character z as sent and drops the alpha of an 8 character one.
+pub fn paint(existing: &str, z: String, mode: &BlendMode) -> Result<String, ContractError> {
+    let [r, g, b, a] = parse_paint(&z).ok_or(ContractError::InvalidPaint {})?;
+    let src = [r, g, b];
+    let dst = grid::parse_color(existing).unwrap_or_default();
+    let out = match mode {
+        BlendMode::Replace if z.len() == grid::Z_LEN => return Ok(z),
+        BlendMode::Replace => src,
+        BlendMode::AlphaOver => mix(dst, src, a),
+        BlendMode::Multiply => {
+            let product = |i: usize| ((dst[i] as u32 * src[i] as u32 + 127) / 255) as u8;
+            mix(dst, [product(0), product(1), product(2)], a)
+        }
+    };
+    Ok(format!("{:02x}{:02x}{:02x}", out[0], out[1], out[2]))
+}
@@ -0,0 +1,80 @@
+use cosmwasm_std::{Addr, Env, Order, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+use crate::msg::{ChangeInfo, GetChangesSinceResponse};
+use crate::state::{Change, CHANGES, NEXT_CHANGE};
+
+/// Number of most recent changes kept in the ring buffer
+pub const CHANGE_LOG_CAPACITY: u64 = 10_000;
+pub const DEFAULT_CHANGES_LIMIT: u32 = 100;
+pub const MAX_CHANGES_LIMIT: u32 = 1_000;
+
+/// Appends a painted point to the change log, dropping the oldest change once the log is full
+pub fn record_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    x: u16,
+    y: u16,
+    z: &str,
+    painter: &Addr,
+) -> StdResult<()> {
+    let seq = NEXT_CHANGE.may_load(storage)?.unwrap_or_default();
+    let change = Change {
+        height: env.block.height,
+        x,
+        y,
+        z: z.to_string(),
+        painter: painter.clone(),
+    };
+    CHANGES.save(storage, seq, &change)?;
+    if seq >= CHANGE_LOG_CAPACITY {
+        CHANGES.remove(storage, seq - CHANGE_LOG_CAPACITY);
+    }
+    NEXT_CHANGE.save(storage, &(seq + 1))
+}
+
+/// Returns the changes painted after `height`, oldest first
+pub fn changes_since(
+    storage: &dyn Storage,
+    height: u64,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GetChangesSinceResponse> {
+    let next = NEXT_CHANGE.may_load(storage)?.unwrap_or_default();
+    let oldest = next.saturating_sub(CHANGE_LOG_CAPACITY);
+    // Heights only grow with the sequence number, so binary search for the first change after height
+    let (mut lo, mut hi) = (oldest, next);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if CHANGES.load(storage, mid)?.height > height {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    // Changes older than the ring buffer may be missing, so clients need to refetch the grid
+    let truncated = oldest > 0 && lo == oldest;
+    let start = match start_after {
+        Some(seq) => lo.max(seq.saturating_add(1)),
+        None => lo,
+    };
+    let limit = limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .min(MAX_CHANGES_LIMIT) as usize;
+    let changes = CHANGES
+        .range(storage, Some(Bound::inclusive(start)), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (seq, change) = item?;
+            Ok(ChangeInfo {
+                seq,
+                height: change.height,
+                x: change.x,
+                y: change.y,
+                z: change.z,
+                painter: change.painter,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetChangesSinceResponse { changes, truncated })
+}
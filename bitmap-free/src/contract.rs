@@ -1,11 +1,22 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+};
 use cw2::set_contract_version;
 
+use crate::access;
+use crate::changes;
+use crate::depth;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{STATE};
+use crate::grid;
+use crate::init;
+use crate::moderation;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, Region};
+use crate::palette;
+use crate::rate_limit;
+use crate::render;
+use crate::state::{BIT_DEPTH, INIT_CREATOR, MODERATORS, PAINTERS, PALETTE, STATE};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:staking-contract";
@@ -14,32 +25,55 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let string_size = (msg.x_size as u32) * (msg.y_size as u32) * 6;
-    let z_values = match msg.z_values {
-        Some(ref z) => {
-            if z.len() != string_size as usize {
-                return Err(ContractError::InvalidZValue {});
-            }
-            z.clone()
-        }
-        None => "0".repeat(string_size as usize),
+    let grid_len = grid::grid_len(msg.x_size, msg.y_size);
+    if msg.chunked_init.unwrap_or(false) {
+        INIT_CREATOR.save(deps.storage, &info.sender)?;
+    }
+    let bit_depth = depth::validate(msg.bit_depth, msg.palette.is_some())?;
+    if bit_depth != depth::RGB_DEPTH {
+        BIT_DEPTH.save(deps.storage, &bit_depth)?;
+    }
+    if let Some(colors) = msg.palette {
+        let colors = palette::validate(colors, None, bit_depth)?;
+        PALETTE.save(deps.storage, &colors, env.block.height)?;
+    }
+    // Points default to "000000" (index 0 when packed), so only explicit z_values are written to storage
+    if let Some(ref z) = msg.z_values {
+        if z.len() != grid_len as usize * grid::Z_LEN || !grid::is_hex(z) {
+            return Err(ContractError::InvalidZValue {});
+        }
+        grid::save_z_values(deps.storage, z, env.block.height)?;
+    }
+    let admin = match msg.admin {
+        Some(ref admin) => deps.api.addr_validate(admin)?,
+        None => info.sender,
     };
+    for moderator in msg.moderators.iter().flatten() {
+        let moderator = deps.api.addr_validate(moderator)?;
+        MODERATORS.save(deps.storage, &moderator, &Empty {})?;
+    }
+    if let Some(ref policy) = msg.access_policy {
+        access::save_policy(deps.branch(), policy)?;
+    }
     let state = crate::state::State {
         x_size: msg.x_size,
         y_size: msg.y_size,
-        z_values: z_values,
+        admin: admin.clone(),
+        rate_limit: msg.rate_limit,
     };
     STATE.save(deps.storage, &state)?;
             Ok(Response::new()
                 .add_attribute("method", "instantiate")
                 .add_attribute("x_size", msg.x_size.to_string())
                 .add_attribute("y_size", msg.y_size.to_string())
+                .add_attribute("admin", admin)
+                .add_attribute("bit_depth", bit_depth.to_string())
             )
 
         }
@@ -48,34 +82,80 @@ pub fn instantiate(
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Set { x, y, z } => execute::set(deps, x, y, z),
+        ExecuteMsg::Set {
+            x,
+            y,
+            z,
+            blend,
+            proof,
+        } => execute::set(deps, env, info, x, y, z, blend, proof),
+        ExecuteMsg::SetAccessPolicy { policy } => access::set_access_policy(deps, info, policy),
+        ExecuteMsg::UpdateAllowlist { add, remove } => {
+            access::update_allowlist(deps, info, add, remove)
+        }
+        ExecuteMsg::InitChunk { offset, data } => init::init_chunk(deps, env, info, offset, data),
+        ExecuteMsg::FinalizeInit {} => init::finalize_init(deps, info),
+        ExecuteMsg::AddModerator { address } => {
+            moderation::add_moderator(deps, env, info, address)
+        }
+        ExecuteMsg::RemoveModerator { address } => {
+            moderation::remove_moderator(deps, env, info, address)
+        }
+        ExecuteMsg::RevertRegion { region, height } => {
+            moderation::revert_region(deps, env, info, region, height)
+        }
+        ExecuteMsg::BanAddress { address } => moderation::ban_address(deps, env, info, address),
+        ExecuteMsg::UnbanAddress { address } => moderation::unban_address(deps, env, info, address),
+        ExecuteMsg::ProtectRegion { region } => moderation::protect_region(deps, env, info, region),
+        ExecuteMsg::UnprotectRegion { id } => moderation::unprotect_region(deps, env, info, id),
+        ExecuteMsg::UpdateRateLimit { rate_limit } => {
+            rate_limit::update_rate_limit(deps, info, rate_limit)
+        }
+        ExecuteMsg::SetPalette { colors } => palette::set_palette(deps, env, info, colors),
+        ExecuteMsg::ProposePalette { colors } => palette::propose_palette(deps, env, info, colors),
+        ExecuteMsg::VotePalette { id } => palette::vote_palette(deps, env, info, id),
     }
 }
 
 pub mod execute {
     use super::*;
 
-    pub fn set(deps: DepsMut, x: u8, y: u8, z: String) -> Result<Response, ContractError> {
-        if x > STATE.load(deps.storage)?.x_size || y > STATE.load(deps.storage)?.y_size {
-            return Err(ContractError::IndexOutOfBounds {});
-        }
-        let new_value_length = z.len();
-        if new_value_length != 6 {
-            return Err(ContractError::InvalidZValue {});
+    use crate::blend;
+    use crate::msg::BlendMode;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn set(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        x: u16,
+        y: u16,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+    ) -> Result<Response, ContractError> {
+        let state = STATE.load(deps.storage)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y)
+            .ok_or(ContractError::IndexOutOfBounds {})?;
+        if blend::parse_paint(&z).is_none() {
+            return Err(ContractError::InvalidPaint {});
         }
-        STATE.update(deps.storage, |mut state |-> Result<_, ContractError>{
-            let y_offset= (y as u32) * (state.x_size as u32) * 6;
-            let x_offset  = (x as u32) * 6;
-            let start = (y_offset + x_offset) as usize;
-            let end = (start + 6) as usize;
-            state.z_values.replace_range(start..end, &z);
-            Ok(state)
-        })?;
+        init::ensure_open(deps.storage)?;
+        moderation::ensure_can_paint(deps.storage, &info.sender, x, y)?;
+        access::check_access(deps.as_ref(), &info.sender, proof)?;
+        rate_limit::check_and_record(deps.storage, &env, &info.sender)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let existing = grid::load_point(deps.storage, grid_len, idx)?;
+        let z = blend::paint(&existing, z, &blend.unwrap_or_default())?;
+        let z = depth::check_paint(deps.storage, z)?;
+        grid::save_point(deps.storage, grid_len, idx, &z, env.block.height)?;
+        PAINTERS.save(deps.storage, idx, &info.sender, env.block.height)?;
+        changes::record_change(deps.storage, &env, x, y, &z, &info.sender)?;
 
         Ok(Response::new()
             .add_attribute("action", "set")
@@ -86,35 +166,250 @@ pub mod execute {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetPoint {x, y} => to_json_binary(&query::get_point(deps, x, y)?),
-        QueryMsg::GetGrid {} => to_json_binary(&query::get_grid(deps)?),
+        QueryMsg::GetGrid { start_after, limit } => {
+            to_json_binary(&query::get_grid(deps, start_after, limit)?)
+        }
+        QueryMsg::GetGridAt {
+            height,
+            start_after,
+            limit,
+        } => to_json_binary(&query::get_grid_at(deps, height, start_after, limit)?),
+        QueryMsg::GetPointAt { x, y, height } => {
+            to_json_binary(&query::get_point_at(deps, x, y, height)?)
+        }
+        QueryMsg::GetRegion {
+            x0,
+            y0,
+            width,
+            height,
+        } => to_json_binary(&query::get_region(deps, x0, y0, width, height)?),
+        QueryMsg::GetRow { y } => to_json_binary(&query::get_row(deps, y)?),
+        QueryMsg::GetPoints { coords } => to_json_binary(&query::get_points(deps, coords)?),
+        QueryMsg::RenderSvg { scale, region } => {
+            to_json_binary(&query::render_svg(deps, scale, region)?)
+        }
+        QueryMsg::RenderImage { format, scale, region } => {
+            to_json_binary(&query::render_image(deps, format, scale, region)?)
+        }
+        QueryMsg::GetAccessPolicy {} => to_json_binary(&access::get_access_policy(deps)?),
+        QueryMsg::GetChangesSince {
+            height,
+            start_after,
+            limit,
+        } => to_json_binary(&changes::changes_since(deps.storage, height, start_after, limit)?),
+        QueryMsg::GetModerators {} => to_json_binary(&moderation::get_moderators(deps)?),
+        QueryMsg::IsBanned { address } => to_json_binary(&moderation::is_banned(deps, address)?),
+        QueryMsg::GetProtectedRegions {} => {
+            to_json_binary(&moderation::get_protected_regions(deps)?)
+        }
+        QueryMsg::GetCooldown { address } => {
+            to_json_binary(&rate_limit::get_cooldown(deps, env, address)?)
+        }
+        QueryMsg::GetModerationLog { start_after, limit } => {
+            to_json_binary(&moderation::get_moderation_log(deps, start_after, limit)?)
+        }
+        QueryMsg::GetPalette {} => to_json_binary(&palette::get_palette(deps)?),
+        QueryMsg::GetPaletteProposal { id } => {
+            to_json_binary(&palette::get_palette_proposal(deps, id)?)
+        }
     }
 }
 
 pub mod query {
     use super::*;
-    use crate::msg::{GetPointResponse,GetGridResponse};
+    use std::collections::btree_map::Entry;
+    use std::collections::BTreeMap;
+
+    use cosmwasm_std::{Binary, StdError};
+
+    use crate::msg::{
+        GetGridResponse, GetPointAtResponse, GetPointResponse, GetPointsResponse, GetRegionResponse, GetRowResponse, RenderSvgResponse,
+        ImageFormat, RenderImageResponse,
+    };
     use crate::state::STATE;
 
-    pub fn get_point(deps: Deps, x: u8, y: u8) -> StdResult<GetPointResponse> {
+    pub fn get_point(deps: Deps, x: u16, y: u16) -> StdResult<GetPointResponse> {
         let state = STATE.load(deps.storage)?;
-        let y_offset= (y as u32) * (state.x_size as u32) * 6;
-        let x_offset  = (x as u32) * 6;
-        let start = (y_offset + x_offset) as usize;
-        let end = (start + 6) as usize;
-        let point = state.z_values[start..end].to_string();
+        let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let point = grid::load_point(deps.storage, grid_len, idx)?;
         Ok(GetPointResponse { point })
     }
 
-    pub fn get_grid(deps: Deps) -> StdResult<GetGridResponse> {
+    pub fn get_grid(
+        deps: Deps,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<GetGridResponse> {
+        grid_page(deps, None, start_after, limit)
+    }
+
+    pub fn get_grid_at(
+        deps: Deps,
+        height: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<GetGridResponse> {
+        grid_page(deps, Some(height), start_after, limit)
+    }
+
+    pub fn get_point_at(deps: Deps, x: u16, y: u16, height: u64) -> StdResult<GetPointAtResponse> {
+        let state = STATE.load(deps.storage)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let chunk = grid::load_chunk_at(deps.storage, grid_len, idx / grid::CHUNK_SIZE, height)?;
+        let start = (idx % grid::CHUNK_SIZE) as usize * grid::Z_LEN;
+        let painter = PAINTERS.may_load_at_height(deps.storage, idx, height.saturating_add(1))?;
+        Ok(GetPointAtResponse {
+            point: chunk[start..start + grid::Z_LEN].to_string(),
+            painter,
+        })
+    }
+
+    // Loads a page of chunks, either current or as of the end of block `height`
+    fn grid_page(
+        deps: Deps,
+        height: Option<u64>,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<GetGridResponse> {
         let state = STATE.load(deps.storage)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let num_chunks = grid::num_chunks(grid_len);
+        let start = start_after.map_or(0, |chunk| chunk.saturating_add(1)).min(num_chunks);
+        let limit = limit.unwrap_or(grid::MAX_GRID_CHUNKS).clamp(1, grid::MAX_GRID_CHUNKS);
+        let end = start.saturating_add(limit).min(num_chunks);
+        let mut z_values = String::new();
+        for chunk in start..end {
+            let z_chunk = match height {
+                Some(height) => grid::load_chunk_at(deps.storage, grid_len, chunk, height)?,
+                None => grid::load_chunk(deps.storage, grid_len, chunk)?,
+            };
+            z_values.push_str(&z_chunk);
+        }
         Ok(GetGridResponse {
             x_size: state.x_size,
             y_size: state.y_size,
-            z_values: state.z_values,
+            offset: start * grid::CHUNK_SIZE,
+            z_values,
+            next_start_after: if end < num_chunks { Some(end - 1) } else { None },
+        })
+    }
+
+    pub fn get_region(
+        deps: Deps,
+        x0: u16,
+        y0: u16,
+        width: u16,
+        height: u16,
+    ) -> StdResult<GetRegionResponse> {
+        let state = STATE.load(deps.storage)?;
+        let z_values =
+            grid::load_region(deps.storage, state.x_size, state.y_size, x0, y0, width, height)?;
+        Ok(GetRegionResponse {
+            x0,
+            y0,
+            width,
+            height,
+            z_values,
+        })
+    }
+
+    /// Loads the region to render, defaulting to the whole grid, and checks the scale
+    fn render_region(
+        deps: Deps,
+        scale: Option<u32>,
+        region: Option<Region>,
+    ) -> StdResult<(String, Region, u32)> {
+        let state = STATE.load(deps.storage)?;
+        let region = region.unwrap_or(Region {
+            x0: 0,
+            y0: 0,
+            width: state.x_size,
+            height: state.y_size,
+        });
+        let scale = scale.unwrap_or(1);
+        if scale == 0 || scale > render::MAX_SCALE {
+            return Err(StdError::generic_err("Invalid scale"));
+        }
+        let z_values = grid::load_region(
+            deps.storage,
+            state.x_size,
+            state.y_size,
+            region.x0,
+            region.y0,
+            region.width,
+            region.height,
+        )?;
+        Ok((z_values, region, scale))
+    }
+
+    pub fn render_svg(
+        deps: Deps,
+        scale: Option<u32>,
+        region: Option<Region>,
+    ) -> StdResult<RenderSvgResponse> {
+        let (z_values, region, scale) = render_region(deps, scale, region)?;
+        Ok(RenderSvgResponse {
+            svg: render::svg(&z_values, region.width, region.height, scale)?,
+        })
+    }
+
+    pub fn render_image(
+        deps: Deps,
+        format: ImageFormat,
+        scale: Option<u32>,
+        region: Option<Region>,
+    ) -> StdResult<RenderImageResponse> {
+        let (z_values, region, scale) = render_region(deps, scale, region)?;
+        let width = region.width as u32 * scale;
+        if width as u64 * (region.height as u32 * scale) as u64 > render::MAX_IMAGE_PIXELS as u64 {
+            return Err(StdError::generic_err("Image too large"));
+        }
+        let rows = render::rgb_rows(&z_values, region.width, region.height, scale);
+        let (image, mime) = match format {
+            ImageFormat::Bmp => (render::bmp(&rows, width), "image/bmp"),
+            ImageFormat::Png => (render::png(&rows, width), "image/png"),
+        };
+        let image = Binary::from(image);
+        Ok(RenderImageResponse {
+            data_uri: format!("data:{};base64,{}", mime, image.to_base64()),
+            image,
         })
     }
+
+    pub fn get_row(deps: Deps, y: u16) -> StdResult<GetRowResponse> {
+        let state = STATE.load(deps.storage)?;
+        let z_values =
+            grid::load_region(deps.storage, state.x_size, state.y_size, 0, y, state.x_size, 1)?;
+        Ok(GetRowResponse { y, z_values })
+    }
+
+    pub fn get_points(deps: Deps, coords: Vec<(u16, u16)>) -> StdResult<GetPointsResponse> {
+        if coords.len() > grid::MAX_POINTS {
+            return Err(StdError::generic_err("Too many points"));
+        }
+        let state = STATE.load(deps.storage)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        // Points often share chunks, so load each chunk only once
+        let mut chunks: BTreeMap<u32, String> = BTreeMap::new();
+        let mut points = Vec::with_capacity(coords.len());
+        for (x, y) in coords {
+            let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+            let z_chunk = match chunks.entry(idx / grid::CHUNK_SIZE) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let chunk = *entry.key();
+                    entry.insert(grid::load_chunk(deps.storage, grid_len, chunk)?)
+                }
+            };
+            let start = (idx % grid::CHUNK_SIZE) as usize * grid::Z_LEN;
+            points.push(z_chunk[start..start + grid::Z_LEN].to_string());
+        }
+        Ok(GetPointsResponse { points })
+    }
 }
 
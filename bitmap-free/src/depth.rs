@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use cosmwasm_std::{Binary, StdError, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::grid;
+use crate::state::{BIT_DEPTH, PALETTE};
+
+/// Bit depth of canvases storing hex RGB values
+pub const RGB_DEPTH: u8 = 24;
+/// Bit depth of palette mode canvases that do not set one, a u8 index per point
+pub const DEFAULT_PALETTE_DEPTH: u8 = 8;
+
+/// The colors a packed canvas can hold, stored as indices of bit_depth bits
+pub struct Packing {
+    pub bit_depth: u8,
+    pub colors: Vec<String>,
+    /// False for grayscale
+    pub is_palette: bool,
+}
+
+/// Checks the bit_depth of an InstantiateMsg. Palette mode canvases store indices of
+/// 1 to 8 bits, other canvases 1 to 8 bit grayscale or 24 bit RGB.
+pub fn validate(bit_depth: Option<u8>, has_palette: bool) -> Result<u8, ContractError> {
+    let default = if has_palette { DEFAULT_PALETTE_DEPTH } else { RGB_DEPTH };
+    match bit_depth.unwrap_or(default) {
+        bit_depth @ (1 | 2 | 4 | 8) => Ok(bit_depth),
+        RGB_DEPTH if !has_palette => Ok(RGB_DEPTH),
+        _ => Err(ContractError::InvalidBitDepth {}),
+    }
+}
+
+pub fn bit_depth(storage: &dyn Storage) -> StdResult<u8> {
+    Ok(BIT_DEPTH.may_load(storage)?.unwrap_or(RGB_DEPTH))
+}
+
+/// Number of colors that fit in an index of bit_depth bits
+pub fn max_colors(bit_depth: u8) -> usize {
+    1 << bit_depth
+}
+
+/// Evenly spaced grays from black to white, the colors of a packed canvas without a palette
+pub fn gray_levels(bit_depth: u8) -> Vec<String> {
+    let max = max_colors(bit_depth) as u32 - 1;
+    (0..=max)
+        .map(|level| {
+            let v = level * 255 / max;
+            format!("{v:02x}{v:02x}{v:02x}")
+        })
+        .collect()
+}
+
+/// Loads the colors of a packed canvas, None for a 24 bit canvas
+pub fn load(storage: &dyn Storage) -> StdResult<Option<Packing>> {
+    let Some(bit_depth) = BIT_DEPTH.may_load(storage)? else {
+        return Ok(None);
+    };
+    let (colors, is_palette) = match PALETTE.may_load(storage)? {
+        Some(palette) => (palette, true),
+        None => (gray_levels(bit_depth), false),
+    };
+    Ok(Some(Packing {
+        bit_depth,
+        colors,
+        is_palette,
+    }))
+}
+
+/// Loads the colors of a packed canvas with the palette in use at `height`
+pub fn load_at(storage: &dyn Storage, height: u64) -> StdResult<Option<Packing>> {
+    let Some(mut packing) = load(storage)? else {
+        return Ok(None);
+    };
+    if let Some(palette) = PALETTE.may_load_at_height(storage, height)? {
+        packing.colors = palette;
+    }
+    Ok(Some(packing))
+}
+
+/// Returns z as stored by a Set, rejecting colors a packed canvas cannot hold
+pub fn check_paint(storage: &dyn Storage, z: String) -> Result<String, ContractError> {
+    let Some(packing) = load(storage)? else {
+        return Ok(z);
+    };
+    let z = z.to_ascii_lowercase();
+    if packing.colors.contains(&z) {
+        return Ok(z);
+    }
+    Err(packing.unavailable())
+}
+
+impl Packing {
+    /// The error for a color the canvas cannot hold
+    fn unavailable(&self) -> ContractError {
+        if self.is_palette {
+            ContractError::NotInPalette {}
+        } else {
+            ContractError::NotGrayLevel {
+                bit_depth: self.bit_depth,
+            }
+        }
+    }
+
+    // Points are packed from the high bits of each byte down
+    fn shift(&self, idx: usize) -> u32 {
+        let bits = self.bit_depth as usize;
+        (8 - bits - idx * bits % 8) as u32
+    }
+
+    /// Resolves a chunk of len packed points to z_values; a missing chunk holds index 0
+    pub fn decode(&self, packed: Option<Binary>, len: u32) -> StdResult<String> {
+        let bytes = packed.map(|packed| packed.to_vec()).unwrap_or_default();
+        let bits = self.bit_depth as usize;
+        let mask = (max_colors(self.bit_depth) - 1) as u8;
+        let mut z_values = String::with_capacity(len as usize * grid::Z_LEN);
+        for idx in 0..len as usize {
+            let byte = bytes.get(idx * bits / 8).copied().unwrap_or_default();
+            let index = (byte >> self.shift(idx)) & mask;
+            let color = self
+                .colors
+                .get(index as usize)
+                .ok_or_else(|| StdError::generic_err("Color index out of range"))?;
+            z_values.push_str(color);
+        }
+        Ok(z_values)
+    }
+
+    /// Packs z_values into color indices
+    pub fn encode(&self, z_values: &str) -> Result<Binary, ContractError> {
+        let lookup: HashMap<&str, u8> = self
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(index, color)| (color.as_str(), index as u8))
+            .collect();
+        let len = z_values.len() / grid::Z_LEN;
+        let mut bytes = vec![0u8; (len * self.bit_depth as usize).div_ceil(8)];
+        for (idx, z) in z_values.as_bytes().chunks(grid::Z_LEN).enumerate() {
+            let z = std::str::from_utf8(z).unwrap_or_default().to_ascii_lowercase();
+            let index = lookup.get(z.as_str()).ok_or_else(|| self.unavailable())?;
+            bytes[idx * self.bit_depth as usize / 8] |= index << self.shift(idx);
+        }
+        Ok(Binary::from(bytes))
+    }
+}
@@ -14,6 +14,48 @@ pub enum ContractError {
     IndexOutOfBounds {},
     #[error("Invalid z value, must be a 6 character hex string")]
     InvalidZValue {},
+    #[error("Invalid z value, must be a 6 (RGB) or 8 (RGBA) character hex string")]
+    InvalidPaint {},
+    #[error("Sender is not eligible to paint")]
+    NotEligible {},
+    #[error("Invalid merkle root, must be a 32 byte hex string")]
+    InvalidMerkleRoot {},
+    #[error("Access policy is not an allowlist")]
+    NotAllowlist {},
+    #[error("Address is banned")]
+    Banned {},
+    #[error("Point is in a protected region")]
+    ProtectedRegion {},
+    #[error("Too many protected regions")]
+    TooManyProtectedRegions {},
+    #[error("The admin and moderators cannot be banned")]
+    CannotBanModerator {},
+    #[error("Region too large")]
+    RegionTooLarge {},
+    #[error("Cooldown active, {remaining} more blocks or seconds to wait")]
+    CooldownActive { remaining: u64 },
+    #[error("Point quota for this epoch exceeded")]
+    QuotaExceeded {},
+    #[error("Canvas is still being initialized")]
+    InitPending {},
+    #[error("Canvas is already initialized")]
+    InitFinalized {},
+    #[error("Canvas is not in palette mode")]
+    NotPaletteMode {},
+    #[error("Color is not in the palette")]
+    NotInPalette {},
+    #[error("Color is not one of the gray levels of a {bit_depth} bit canvas")]
+    NotGrayLevel { bit_depth: u8 },
+    #[error("Invalid bit depth, must be 1, 2, 4 or 8, or 24 without a palette")]
+    InvalidBitDepth {},
+    #[error("Invalid palette: {reason}")]
+    InvalidPalette { reason: String },
+    #[error("Palette proposal not found")]
+    ProposalNotFound {},
+    #[error("Palette proposal is stale, the palette changed since it was made")]
+    StaleProposal {},
+    #[error("Already voted")]
+    AlreadyVoted {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
@@ -0,0 +1,236 @@
+use cosmwasm_std::{StdError, StdResult, Storage};
+
+use crate::depth;
+use crate::error::ContractError;
+use crate::state::{PACKED_CHUNKS, Z_CHUNKS};
+
+/// Number of points stored in each z_values chunk
+pub const CHUNK_SIZE: u32 = 1024;
+/// Number of characters used by each point in z_values
+pub const Z_LEN: usize = 6;
+/// Default and maximum number of chunks returned by a single GetGrid page.
+/// Any grid that fits in 255x255 is returned in one page.
+pub const MAX_GRID_CHUNKS: u32 = 64;
+/// Maximum number of points returned by GetRegion and GetRow
+pub const MAX_REGION_POINTS: u32 = 65536;
+/// Maximum number of coordinates accepted by GetPoints
+pub const MAX_POINTS: usize = 256;
+
+/// Parses a 6 character hex string into RGB
+pub fn parse_color(z: &str) -> Option<[u8; 3]> {
+    if z.len() != Z_LEN || !z.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&z[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Checks that z_values is made of hex digits only
+pub fn is_hex(z_values: &str) -> bool {
+    z_values.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+pub fn grid_len(x_size: u16, y_size: u16) -> u32 {
+    (x_size as u32) * (y_size as u32)
+}
+
+/// Returns the grid index of (x, y), or None if it falls outside the grid
+pub fn point_index(x_size: u16, y_size: u16, x: u16, y: u16) -> Option<u32> {
+    if x >= x_size || y >= y_size {
+        return None;
+    }
+    Some((y as u32) * (x_size as u32) + (x as u32))
+}
+
+pub fn num_chunks(grid_len: u32) -> u32 {
+    grid_len.div_ceil(CHUNK_SIZE)
+}
+
+/// Number of points in a chunk; only the last chunk can be shorter than CHUNK_SIZE
+pub fn chunk_len(grid_len: u32, chunk: u32) -> u32 {
+    (grid_len - chunk * CHUNK_SIZE).min(CHUNK_SIZE)
+}
+
+/// Loads a chunk of z_values. Chunks that were never written hold "000000" points,
+/// or the first palette color on a palette mode canvas.
+pub fn load_chunk(storage: &dyn Storage, grid_len: u32, chunk: u32) -> StdResult<String> {
+    if let Some(packing) = depth::load(storage)? {
+        let packed = PACKED_CHUNKS.may_load(storage, chunk)?;
+        return packing.decode(packed, chunk_len(grid_len, chunk));
+    }
+    match Z_CHUNKS.may_load(storage, chunk)? {
+        Some(z) => Ok(z),
+        None => Ok("0".repeat(chunk_len(grid_len, chunk) as usize * Z_LEN)),
+    }
+}
+
+/// Loads a chunk of z_values as it was at the end of block `height`
+pub fn load_chunk_at(
+    storage: &dyn Storage,
+    grid_len: u32,
+    chunk: u32,
+    height: u64,
+) -> StdResult<String> {
+    // Snapshots at a height hold the values from before that block's changes
+    let height = height.saturating_add(1);
+    // Packed points are resolved with the palette of the time
+    if let Some(packing) = depth::load_at(storage, height)? {
+        let packed = PACKED_CHUNKS.may_load_at_height(storage, chunk, height)?;
+        return packing.decode(packed, chunk_len(grid_len, chunk));
+    }
+    match Z_CHUNKS.may_load_at_height(storage, chunk, height)? {
+        Some(z) => Ok(z),
+        None => Ok("0".repeat(chunk_len(grid_len, chunk) as usize * Z_LEN)),
+    }
+}
+
+/// Loads a chunk as stored at the end of block `height`, with packed points resolved
+/// with the current colors, so saving it back restores the stored indices even if
+/// the palette changed since
+pub fn load_stored_chunk_at(
+    storage: &dyn Storage,
+    grid_len: u32,
+    chunk: u32,
+    height: u64,
+) -> StdResult<String> {
+    match depth::load(storage)? {
+        Some(packing) => {
+            let packed = PACKED_CHUNKS.may_load_at_height(storage, chunk, height.saturating_add(1))?;
+            packing.decode(packed, chunk_len(grid_len, chunk))
+        }
+        None => load_chunk_at(storage, grid_len, chunk, height),
+    }
+}
+
+/// Stores a chunk of z_values, packed at the bit depth of the canvas
+pub fn save_chunk(
+    storage: &mut dyn Storage,
+    chunk: u32,
+    z_values: &str,
+    height: u64,
+) -> Result<(), ContractError> {
+    match depth::load(storage)? {
+        Some(packing) => PACKED_CHUNKS.save(storage, chunk, &packing.encode(z_values)?, height)?,
+        None => Z_CHUNKS.save(storage, chunk, &z_values.to_string(), height)?,
+    }
+    Ok(())
+}
+
+/// The value of a point that was never painted
+pub fn blank_point(storage: &dyn Storage) -> StdResult<String> {
+    Ok(match depth::load(storage)? {
+        Some(packing) => packing.colors[0].clone(),
+        None => "0".repeat(Z_LEN),
+    })
+}
+
+/// Splits the initial z_values into chunks and stores them
+pub fn save_z_values(
+    storage: &mut dyn Storage,
+    z_values: &str,
+    height: u64,
+) -> Result<(), ContractError> {
+    let chunk_chars = CHUNK_SIZE as usize * Z_LEN;
+    for (chunk, start) in (0..z_values.len()).step_by(chunk_chars).enumerate() {
+        let end = (start + chunk_chars).min(z_values.len());
+        save_chunk(storage, chunk as u32, &z_values[start..end], height)?;
+    }
+    Ok(())
+}
+
+pub fn load_point(storage: &dyn Storage, grid_len: u32, idx: u32) -> StdResult<String> {
+    let chunk = load_chunk(storage, grid_len, idx / CHUNK_SIZE)?;
+    let start = (idx % CHUNK_SIZE) as usize * Z_LEN;
+    Ok(chunk[start..start + Z_LEN].to_string())
+}
+
+/// Loads len consecutive points starting at grid index idx
+pub fn load_range(storage: &dyn Storage, grid_len: u32, idx: u32, len: u32) -> StdResult<String> {
+    let mut z_values = String::with_capacity(len as usize * Z_LEN);
+    let end = idx + len;
+    let mut idx = idx;
+    while idx < end {
+        let chunk = idx / CHUNK_SIZE;
+        let chunk_end = ((chunk + 1) * CHUNK_SIZE).min(end);
+        let z_chunk = load_chunk(storage, grid_len, chunk)?;
+        let start = (idx % CHUNK_SIZE) as usize * Z_LEN;
+        z_values.push_str(&z_chunk[start..start + (chunk_end - idx) as usize * Z_LEN]);
+        idx = chunk_end;
+    }
+    Ok(z_values)
+}
+
+/// Loads a rectangle of points row by row. The rectangle must fit inside the grid
+/// and hold at most MAX_REGION_POINTS points.
+pub fn load_region(
+    storage: &dyn Storage,
+    x_size: u16,
+    y_size: u16,
+    x0: u16,
+    y0: u16,
+    width: u16,
+    height: u16,
+) -> StdResult<String> {
+    if x0 as u32 + width as u32 > x_size as u32 || y0 as u32 + height as u32 > y_size as u32 {
+        return Err(out_of_bounds());
+    }
+    if (width as u32) * (height as u32) > MAX_REGION_POINTS {
+        return Err(StdError::generic_err("Region too large"));
+    }
+    let grid_len = grid_len(x_size, y_size);
+    let mut z_values = String::with_capacity((width as usize) * (height as usize) * Z_LEN);
+    for y in y0..y0 + height {
+        let idx = (y as u32) * (x_size as u32) + (x0 as u32);
+        z_values.push_str(&load_range(storage, grid_len, idx, width as u32)?);
+    }
+    Ok(z_values)
+}
+
+/// Writes consecutive points starting at grid index idx, across chunks if needed
+pub fn save_range(
+    storage: &mut dyn Storage,
+    grid_len: u32,
+    idx: u32,
+    z_values: &str,
+    height: u64,
+) -> Result<(), ContractError> {
+    let end = idx + (z_values.len() / Z_LEN) as u32;
+    let mut idx = idx;
+    let mut pos = 0;
+    while idx < end {
+        let chunk = idx / CHUNK_SIZE;
+        let chunk_end = ((chunk + 1) * CHUNK_SIZE).min(end);
+        let start = (idx % CHUNK_SIZE) as usize * Z_LEN;
+        let len = (chunk_end - idx) as usize * Z_LEN;
+        let data = &z_values[pos..pos + len];
+        // Whole chunks are written without loading the old values
+        let z_chunk = if len == chunk_len(grid_len, chunk) as usize * Z_LEN {
+            data.to_string()
+        } else {
+            let mut z_chunk = load_chunk(storage, grid_len, chunk)?;
+            z_chunk.replace_range(start..start + len, data);
+            z_chunk
+        };
+        save_chunk(storage, chunk, &z_chunk, height)?;
+        pos += len;
+        idx = chunk_end;
+    }
+    Ok(())
+}
+
+pub fn save_point(
+    storage: &mut dyn Storage,
+    grid_len: u32,
+    idx: u32,
+    z: &str,
+    height: u64,
+) -> Result<(), ContractError> {
+    let mut chunk = load_chunk(storage, grid_len, idx / CHUNK_SIZE)?;
+    let start = (idx % CHUNK_SIZE) as usize * Z_LEN;
+    chunk.replace_range(start..start + Z_LEN, z);
+    save_chunk(storage, idx / CHUNK_SIZE, &chunk, height)
+}
+
+pub fn out_of_bounds() -> StdError {
+    StdError::generic_err("Index out of bounds")
+}
@@ -0,0 +1,51 @@
+use cosmwasm_std::{Addr, DepsMut, Env, MessageInfo, Response, Storage};
+
+use crate::error::ContractError;
+use crate::grid;
+use crate::state::{INIT_CREATOR, STATE};
+
+/// Rejects Set until the creator has sent FinalizeInit
+pub fn ensure_open(storage: &dyn Storage) -> Result<(), ContractError> {
+    if INIT_CREATOR.exists(storage) {
+        return Err(ContractError::InitPending {});
+    }
+    Ok(())
+}
+
+fn ensure_creator(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    match INIT_CREATOR.may_load(storage)? {
+        Some(creator) if creator == *sender => Ok(()),
+        Some(_) => Err(ContractError::Unauthorized {}),
+        None => Err(ContractError::InitFinalized {}),
+    }
+}
+
+pub fn init_chunk(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offset: u32,
+    data: String,
+) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+    let len = data.len() / grid::Z_LEN;
+    if len == 0 || data.len() != len * grid::Z_LEN || !grid::is_hex(&data) {
+        return Err(ContractError::InvalidZValue {});
+    }
+    let state = STATE.load(deps.storage)?;
+    let grid_len = grid::grid_len(state.x_size, state.y_size);
+    if offset as u64 + len as u64 > grid_len as u64 {
+        return Err(ContractError::IndexOutOfBounds {});
+    }
+    grid::save_range(deps.storage, grid_len, offset, &data, env.block.height)?;
+    Ok(Response::new()
+        .add_attribute("action", "init_chunk")
+        .add_attribute("offset", offset.to_string())
+        .add_attribute("points", len.to_string()))
+}
+
+pub fn finalize_init(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_creator(deps.storage, &info.sender)?;
+    INIT_CREATOR.remove(deps.storage);
+    Ok(Response::new().add_attribute("action", "finalize_init"))
+}
@@ -38,13 +38,13 @@ mod tests {
 
     use crate::msg::{QueryMsg, GetGridResponse};
 
-    fn proper_instantiate(x_size: u8, y_size: u8, z_values: String) -> (App, CwTemplateContract) {
+    fn proper_instantiate(x_size: u16, y_size: u16, z_values: String) -> (App, CwTemplateContract) {
         let mut app = mock_app();
         let cw_template_id = app.store_code(contract_template());
         let msg = if z_values.is_empty() {
-            InstantiateMsg { x_size, y_size, z_values: None }
+            InstantiateMsg { x_size, y_size, z_values: None, admin: None, moderators: None, rate_limit: None, access_policy: None, chunked_init: None, palette: None, bit_depth: None }
         } else {
-            InstantiateMsg { x_size, y_size, z_values: Some(z_values) }
+            InstantiateMsg { x_size, y_size, z_values: Some(z_values), admin: None, moderators: None, rate_limit: None, access_policy: None, chunked_init: None, palette: None, bit_depth: None }
         };
         let cw_template_contract_addr = app
             .instantiate_contract(
@@ -67,7 +67,7 @@ mod tests {
         let (app, cw_template_contract) = proper_instantiate(2, 2, custom.clone());
         let res: GetGridResponse = app.wrap().query_wasm_smart(
             cw_template_contract.addr(),
-            &QueryMsg::GetGrid {},
+            &QueryMsg::GetGrid { start_after: None, limit: None },
         ).unwrap();
     assert_eq!(res.x_size, 2);
     assert_eq!(res.y_size, 2);
@@ -82,7 +82,7 @@ mod tests {
         let (app, cw_template_contract) = proper_instantiate(x_size, y_size, String::new());
         let res: GetGridResponse = app.wrap().query_wasm_smart(
             cw_template_contract.addr(),
-            &QueryMsg::GetGrid {},
+            &QueryMsg::GetGrid { start_after: None, limit: None },
         ).unwrap();
         assert_eq!(res.x_size, x_size);
         assert_eq!(res.y_size, y_size);
@@ -96,7 +96,7 @@ mod tests {
         let (mut app, cw_template_contract) = proper_instantiate(x_size, y_size, String::new());
         // Set point (1,1) to "aabbcc"
         let z_value = "aabbcc".to_string();
-        let msg = crate::msg::ExecuteMsg::Set { x: 1, y: 1, z: z_value.clone() };
+        let msg = crate::msg::ExecuteMsg::Set { x: 1, y: 1, z: z_value.clone(), blend: None, proof: None };
         let cosmos_msg = cw_template_contract.call(msg).unwrap();
         app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
         // Query point (1,1) and check value
@@ -109,13 +109,817 @@ mod tests {
         // Query grid and check the value at the correct offset
         let grid_res: crate::msg::GetGridResponse = app.wrap().query_wasm_smart(
             cw_template_contract.addr(),
-            &crate::msg::QueryMsg::GetGrid {},
+            &crate::msg::QueryMsg::GetGrid { start_after: None, limit: None },
         ).unwrap();
         let mut expected = "0".repeat(x_size as usize * y_size as usize * 6);
-        let start = (1 * x_size as usize * 6) + (1 * 6);
+        let start = (x_size as usize * 6) + 6;
         let end = start + 6;
         expected.replace_range(start..end, &z_value);
         assert_eq!(grid_res.z_values, expected);
     }
 
+    #[test]
+    fn test_large_grid_pages() {
+        let (mut app, cw_template_contract) = proper_instantiate(1024, 1024, String::new());
+        let msg = crate::msg::ExecuteMsg::Set { x: 1023, y: 1023, z: "aabbcc".to_string(), blend: None, proof: None };
+        app.execute(Addr::unchecked(USER), cw_template_contract.call(msg).unwrap()).unwrap();
+
+        // Out of bounds coordinates are rejected
+        let msg = crate::msg::ExecuteMsg::Set { x: 1024, y: 0, z: "aabbcc".to_string(), blend: None, proof: None };
+        app.execute(Addr::unchecked(USER), cw_template_contract.call(msg).unwrap()).unwrap_err();
+
+        let mut z_values = String::new();
+        let mut start_after = None;
+        loop {
+            let res: GetGridResponse = app.wrap().query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetGrid { start_after, limit: None },
+            ).unwrap();
+            assert_eq!(res.offset as usize * 6, z_values.len());
+            z_values.push_str(&res.z_values);
+            start_after = res.next_start_after;
+            if start_after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(z_values.len(), 1024 * 1024 * 6);
+        assert_eq!(&z_values[z_values.len() - 6..], "aabbcc");
+    }
+
+    #[test]
+    fn test_u8_message_shape() {
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
+        let msg: InstantiateMsg = cosmwasm_std::from_json(br#"{"x_size":2,"y_size":2,"z_values":null}"#).unwrap();
+        let addr = app
+            .instantiate_contract(cw_template_id, Addr::unchecked(ADMIN), &msg, &[], "test", None)
+            .unwrap();
+        let res: GetGridResponse = app.wrap().query_wasm_smart(
+            addr,
+            &cosmwasm_std::from_json::<QueryMsg>(br#"{"get_grid":{}}"#).unwrap(),
+        ).unwrap();
+        assert_eq!(res.z_values, "0".repeat(2 * 2 * 6));
+        assert_eq!(res.next_start_after, None);
+    }
+
+    #[test]
+    fn test_region_row_and_points() {
+        // 3x2 grid: row 0 is 111111 222222 333333, row 1 is 444444 555555 666666
+        let custom = "111111222222333333444444555555666666".to_string();
+        let (app, cw_template_contract) = proper_instantiate(3, 2, custom);
+        let res: crate::msg::GetRegionResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetRegion { x0: 1, y0: 0, width: 2, height: 2 },
+        ).unwrap();
+        assert_eq!(res.z_values, "222222333333555555666666");
+
+        let res: crate::msg::GetRowResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetRow { y: 1 },
+        ).unwrap();
+        assert_eq!(res.z_values, "444444555555666666");
+
+        let res: crate::msg::GetPointsResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPoints { coords: vec![(2, 1), (0, 0)] },
+        ).unwrap();
+        assert_eq!(res.points, vec!["666666".to_string(), "111111".to_string()]);
+
+        // Regions past the edge of the grid, and requests over the limits, are rejected
+        app.wrap().query_wasm_smart::<crate::msg::GetRegionResponse>(
+            cw_template_contract.addr(),
+            &QueryMsg::GetRegion { x0: 2, y0: 0, width: 2, height: 1 },
+        ).unwrap_err();
+        app.wrap().query_wasm_smart::<crate::msg::GetPointsResponse>(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPoints { coords: vec![(0, 0); crate::grid::MAX_POINTS + 1] },
+        ).unwrap_err();
+    }
+
+    #[test]
+    fn test_region_size_limit() {
+        let (app, cw_template_contract) = proper_instantiate(1024, 1024, String::new());
+        let res: crate::msg::GetRegionResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetRegion { x0: 768, y0: 768, width: 256, height: 256 },
+        ).unwrap();
+        assert_eq!(res.z_values.len(), 256 * 256 * 6);
+        app.wrap().query_wasm_smart::<crate::msg::GetRegionResponse>(
+            cw_template_contract.addr(),
+            &QueryMsg::GetRegion { x0: 0, y0: 0, width: 257, height: 256 },
+        ).unwrap_err();
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2, String::new());
+        let set = |app: &mut App, x: u16, z: &str| {
+            let msg = crate::msg::ExecuteMsg::Set { x, y: 0, z: z.to_string(), blend: None, proof: None };
+            app.execute(Addr::unchecked(USER), cw_template_contract.call(msg).unwrap()).unwrap();
+        };
+        set(&mut app, 0, "111111");
+        let synced_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        set(&mut app, 1, "222222");
+        set(&mut app, 0, "333333");
+
+        let res: crate::msg::GetChangesSinceResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetChangesSince { height: synced_height, start_after: None, limit: Some(1) },
+        ).unwrap();
+        assert!(!res.truncated);
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!(res.changes[0].z, "222222");
+        assert_eq!(res.changes[0].painter, Addr::unchecked(USER));
+        assert_eq!(res.changes[0].height, synced_height + 1);
+
+        let res: crate::msg::GetChangesSinceResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetChangesSince {
+                height: synced_height,
+                start_after: Some(res.changes[0].seq),
+                limit: None,
+            },
+        ).unwrap();
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!((res.changes[0].x, res.changes[0].z.as_str()), (0, "333333"));
+    }
+
+    #[test]
+    fn test_grid_at_height() {
+        let custom = "abcdef012345fedcba987654".to_string();
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2, custom.clone());
+        let instantiate_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        let msg = crate::msg::ExecuteMsg::Set { x: 1, y: 0, z: "aabbcc".to_string(), blend: None, proof: None };
+        app.execute(Addr::unchecked(USER), cw_template_contract.call(msg).unwrap()).unwrap();
+        let painted_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        let msg = crate::msg::ExecuteMsg::Set { x: 1, y: 0, z: "ddeeff".to_string(), blend: None, proof: None };
+        app.execute(Addr::unchecked(ADMIN), cw_template_contract.call(msg).unwrap()).unwrap();
+
+        let res: GetGridResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetGridAt { height: instantiate_height, start_after: None, limit: None },
+        ).unwrap();
+        assert_eq!(res.z_values, custom);
+
+        let res: crate::msg::GetPointAtResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPointAt { x: 1, y: 0, height: instantiate_height },
+        ).unwrap();
+        assert_eq!(res.point, "012345");
+        assert_eq!(res.painter, None);
+
+        let res: crate::msg::GetPointAtResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPointAt { x: 1, y: 0, height: painted_height },
+        ).unwrap();
+        assert_eq!(res.point, "aabbcc");
+        assert_eq!(res.painter, Some(Addr::unchecked(USER)));
+
+        let res: GetGridResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetGridAt { height: painted_height + 1, start_after: None, limit: None },
+        ).unwrap();
+        assert_eq!(&res.z_values[6..12], "ddeeff");
+    }
+
+    #[test]
+    fn test_moderation() {
+        use crate::msg::{ExecuteMsg, Region};
+        let moderator = MockApi::default().addr_make("moderator");
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
+        let msg = InstantiateMsg {
+            x_size: 2,
+            y_size: 2,
+            z_values: Some("abcdef012345fedcba987654".to_string()),
+            admin: None,
+            moderators: Some(vec![moderator.to_string()]),
+            rate_limit: None,
+            access_policy: None,
+            chunked_init: None,
+            palette: None,
+            bit_depth: None,
+        };
+        let addr = app
+            .instantiate_contract(cw_template_id, Addr::unchecked(ADMIN), &msg, &[], "test", None)
+            .unwrap();
+        let cw_template_contract = CwTemplateContract(addr);
+        let execute = |app: &mut App, sender: &str, msg: ExecuteMsg| {
+            app.execute(Addr::unchecked(sender), cw_template_contract.call(msg).unwrap())
+        };
+        let user = MockApi::default().addr_make(USER);
+
+        execute(&mut app, user.as_str(), ExecuteMsg::Set { x: 1, y: 1, z: "111111".to_string(), blend: None, proof: None }).unwrap();
+        let clean_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        execute(&mut app, user.as_str(), ExecuteMsg::Set { x: 0, y: 0, z: "ff00ff".to_string(), blend: None, proof: None }).unwrap();
+
+        // Only moderators can moderate
+        let region = Region { x0: 0, y0: 0, width: 2, height: 2 };
+        let revert = ExecuteMsg::RevertRegion { region: region.clone(), height: Some(clean_height) };
+        execute(&mut app, user.as_str(), revert.clone()).unwrap_err();
+        execute(&mut app, moderator.as_str(), revert).unwrap();
+        let res: GetGridResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetGrid { start_after: None, limit: None },
+        ).unwrap();
+        assert_eq!(res.z_values, "abcdef012345fedcba111111");
+
+        execute(&mut app, moderator.as_str(), ExecuteMsg::BanAddress { address: user.to_string() }).unwrap();
+        let err = execute(&mut app, user.as_str(), ExecuteMsg::Set { x: 1, y: 0, z: "ff00ff".to_string(), blend: None, proof: None })
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Address is banned");
+
+        let protected = Region { x0: 0, y0: 0, width: 1, height: 1 };
+        execute(&mut app, ADMIN, ExecuteMsg::ProtectRegion { region: protected }).unwrap();
+        let err = execute(&mut app, ADMIN, ExecuteMsg::Set { x: 0, y: 0, z: "ff00ff".to_string(), blend: None, proof: None })
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Point is in a protected region");
+        execute(&mut app, ADMIN, ExecuteMsg::Set { x: 1, y: 0, z: "ff00ff".to_string(), blend: None, proof: None }).unwrap();
+
+        execute(&mut app, moderator.as_str(), ExecuteMsg::RevertRegion { region, height: None }).unwrap();
+        let res: GetGridResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetGrid { start_after: None, limit: None },
+        ).unwrap();
+        assert_eq!(res.z_values, "0".repeat(24));
+
+        let res: crate::msg::GetModerationLogResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetModerationLog { start_after: None, limit: None },
+        ).unwrap();
+        assert_eq!(res.entries.len(), 4);
+        assert_eq!(res.entries[0].moderator, moderator);
+        assert_eq!(res.entries[2].moderator, Addr::unchecked(ADMIN));
+
+        // Moderators, and the admin, cannot be banned
+        let err = execute(&mut app, ADMIN, ExecuteMsg::BanAddress { address: moderator.to_string() })
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "The admin and moderators cannot be banned");
+
+        // Every Set checks the protected regions, so there is a bounded number of them
+        for _ in 1..crate::moderation::MAX_PROTECTED_REGIONS {
+            let region = Region { x0: 1, y0: 1, width: 1, height: 1 };
+            execute(&mut app, moderator.as_str(), ExecuteMsg::ProtectRegion { region }).unwrap();
+        }
+        let region = Region { x0: 1, y0: 1, width: 1, height: 1 };
+        let err = execute(&mut app, moderator.as_str(), ExecuteMsg::ProtectRegion { region }).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Too many protected regions");
+    }
+
+    #[test]
+    fn test_cooldown_and_quota() {
+        use crate::msg::{ExecuteMsg, GetCooldownResponse, Interval, Quota, RateLimit};
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
+        let msg = InstantiateMsg {
+            x_size: 2,
+            y_size: 2,
+            z_values: None,
+            admin: None,
+            moderators: None,
+            rate_limit: Some(RateLimit {
+                cooldown: Some(Interval::Blocks(5)),
+                quota: Some(Quota { epoch: Interval::Blocks(1000), max_points: 2 }),
+            }),
+            access_policy: None,
+            chunked_init: None,
+            palette: None,
+            bit_depth: None,
+        };
+        let addr = app
+            .instantiate_contract(cw_template_id, Addr::unchecked(ADMIN), &msg, &[], "test", None)
+            .unwrap();
+        let cw_template_contract = CwTemplateContract(addr);
+        let user = MockApi::default().addr_make(USER);
+        let set = |app: &mut App| {
+            let msg = ExecuteMsg::Set { x: 0, y: 0, z: "aabbcc".to_string(), blend: None, proof: None };
+            app.execute(user.clone(), cw_template_contract.call(msg).unwrap())
+        };
+        let advance = |app: &mut App, blocks: u64| {
+            app.update_block(|block| block.height += blocks);
+        };
+        // Keep all paints inside the same epoch
+        let height = app.block_info().height;
+        advance(&mut app, 1000 - height % 1000);
+
+        set(&mut app).unwrap();
+        let err = set(&mut app).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Cooldown active, 5 more blocks or seconds to wait");
+        advance(&mut app, 2);
+        let res: GetCooldownResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetCooldown { address: user.to_string() },
+        ).unwrap();
+        assert_eq!(res.remaining, Some(Interval::Blocks(3)));
+        assert_eq!(res.points_left, Some(1));
+        assert_eq!(res.epoch_remaining, Some(Interval::Blocks(998)));
+
+        advance(&mut app, 3);
+        set(&mut app).unwrap();
+        advance(&mut app, 5);
+        let err = set(&mut app).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Point quota for this epoch exceeded");
+
+        // The admin can lift the limits
+        let msg = ExecuteMsg::UpdateRateLimit { rate_limit: None };
+        app.execute(Addr::unchecked(ADMIN), cw_template_contract.call(msg).unwrap()).unwrap();
+        set(&mut app).unwrap();
+
+        // A cooldown too long to ever end rejects later paints instead of overflowing
+        let rate_limit = RateLimit { cooldown: Some(Interval::Blocks(u64::MAX)), quota: None };
+        let msg = ExecuteMsg::UpdateRateLimit { rate_limit: Some(rate_limit) };
+        app.execute(Addr::unchecked(ADMIN), cw_template_contract.call(msg).unwrap()).unwrap();
+        advance(&mut app, 10);
+        let err = set(&mut app).unwrap_err();
+        assert!(err.root_cause().to_string().starts_with("Cooldown active"));
+    }
+
+    #[test]
+    fn test_allowlist_and_merkle_policies() {
+        use crate::msg::{AccessPolicy, ExecuteMsg};
+        use sha2::{Digest, Sha256};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2, String::new());
+        let user = MockApi::default().addr_make(USER);
+        let other = MockApi::default().addr_make("other");
+        let execute = |app: &mut App, sender: &Addr, msg: ExecuteMsg| {
+            app.execute(sender.clone(), cw_template_contract.call(msg).unwrap())
+        };
+        let set = |proof: Option<Vec<String>>| ExecuteMsg::Set { x: 0, y: 0, z: "aabbcc".to_string(), blend: None, proof };
+
+        // Only the admin manages the policy
+        let policy = ExecuteMsg::SetAccessPolicy { policy: AccessPolicy::Allowlist(vec![user.clone()]) };
+        execute(&mut app, &user, policy.clone()).unwrap_err();
+        execute(&mut app, &Addr::unchecked(ADMIN), policy).unwrap();
+        execute(&mut app, &user, set(None)).unwrap();
+        let err = execute(&mut app, &other, set(None)).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Sender is not eligible to paint");
+        let update = ExecuteMsg::UpdateAllowlist { add: vec![other.to_string()], remove: vec![user.to_string()] };
+        execute(&mut app, &Addr::unchecked(ADMIN), update).unwrap();
+        execute(&mut app, &other, set(None)).unwrap();
+        execute(&mut app, &user, set(None)).unwrap_err();
+
+        // Two leaf tree: the proof for each leaf is the other leaf
+        let leaf = |addr: &Addr| -> [u8; 32] { Sha256::digest(addr.as_bytes()).into() };
+        let (a, b) = (leaf(&user), leaf(&other));
+        let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+        let root: [u8; 32] = Sha256::new().chain_update(lo).chain_update(hi).finalize().into();
+        let policy = AccessPolicy::MerkleAllowlist { root: hex::encode(root) };
+        execute(&mut app, &Addr::unchecked(ADMIN), ExecuteMsg::SetAccessPolicy { policy }).unwrap();
+        execute(&mut app, &user, set(Some(vec![hex::encode(b)]))).unwrap();
+        execute(&mut app, &user, set(Some(vec![hex::encode(a)]))).unwrap_err();
+        execute(&mut app, &user, set(None)).unwrap_err();
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let custom = "aabbccaabbcc112233000000000000000000".to_string();
+        let (mut app, cw_template_contract) = proper_instantiate(3, 2, custom);
+        let res: crate::msg::RenderSvgResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::RenderSvg { scale: Some(10), region: None },
+        ).unwrap();
+        assert_eq!(
+            res.svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="30" height="20" viewBox="0 0 3 2" shape-rendering="crispEdges">"#,
+                r##"<rect x="0" y="0" width="2" height="1" fill="#aabbcc"/>"##,
+                r##"<rect x="2" y="0" width="1" height="1" fill="#112233"/>"##,
+                r##"<rect x="0" y="1" width="3" height="1" fill="#000000"/>"##,
+                "</svg>",
+            )
+        );
+
+        let region = crate::msg::Region { x0: 2, y0: 0, width: 1, height: 1 };
+        let res: crate::msg::RenderSvgResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::RenderSvg { scale: None, region: Some(region) },
+        ).unwrap();
+        assert!(res.svg.contains(r##"<rect x="0" y="0" width="1" height="1" fill="#112233"/>"##));
+
+        // Points must be hex colors
+        let msg = crate::msg::ExecuteMsg::Set { x: 0, y: 0, z: "<svg/>".to_string(), blend: None, proof: None };
+        app.execute(Addr::unchecked(USER), cw_template_contract.call(msg).unwrap()).unwrap_err();
+
+        // Every point of a striped grid is its own rect, too many for one document
+        let (app, cw_template_contract) = proper_instantiate(200, 100, "ffffff000000".repeat(10000));
+        let err = app
+            .wrap()
+            .query_wasm_smart::<crate::msg::RenderSvgResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::RenderSvg { scale: None, region: None },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Image too large"));
+        let region = crate::msg::Region { x0: 0, y0: 0, width: 100, height: 100 };
+        app.wrap()
+            .query_wasm_smart::<crate::msg::RenderSvgResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::RenderSvg { scale: None, region: Some(region) },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_render_image() {
+        let custom = "aabbccaabbcc112233000000000000000000".to_string();
+        let (app, cw_template_contract) = proper_instantiate(3, 2, custom);
+        let render = |format, scale| -> crate::msg::RenderImageResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::RenderImage { format, scale: Some(scale), region: None },
+                )
+                .unwrap()
+        };
+
+        let res = render(crate::msg::ImageFormat::Bmp, 2);
+        let bmp = res.image.as_slice();
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32::from_le_bytes(bmp[18..22].try_into().unwrap()), 6);
+        assert_eq!(u32::from_le_bytes(bmp[22..26].try_into().unwrap()), 4);
+        // 6 pixels of 3 bytes, padded to 20 bytes per row
+        assert_eq!(bmp.len(), 54 + 20 * 4);
+        // The last row is stored first; the top left pixel is BGR aabbcc
+        assert_eq!(&bmp[54 + 20 * 3..54 + 20 * 3 + 3], &[0xcc, 0xbb, 0xaa]);
+        assert_eq!(res.data_uri, format!("data:image/bmp;base64,{}", res.image.to_base64()));
+
+        let res = render(crate::msg::ImageFormat::Png, 10);
+        let png = res.image.as_slice();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 30);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 20);
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+        assert!(res.data_uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<crate::msg::RenderImageResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::RenderImage { format: crate::msg::ImageFormat::Png, scale: Some(0), region: None },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid scale"));
+    }
+
+    #[test]
+    fn test_chunked_init() {
+        use crate::msg::{ExecuteMsg, GetPointResponse};
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
+        // 40x40 grid spans two chunks
+        let msg = InstantiateMsg {
+            x_size: 40,
+            y_size: 40,
+            z_values: None,
+            admin: None,
+            moderators: None,
+            rate_limit: None,
+            access_policy: None,
+            chunked_init: Some(true),
+            palette: None,
+            bit_depth: None,
+        };
+        let addr = app
+            .instantiate_contract(cw_template_id, Addr::unchecked(ADMIN), &msg, &[], "test", None)
+            .unwrap();
+        let cw_template_contract = CwTemplateContract(addr);
+        let execute = |app: &mut App, sender: &str, msg: ExecuteMsg| {
+            app.execute(Addr::unchecked(sender), cw_template_contract.call(msg).unwrap())
+        };
+        let point = |app: &App, x: u16, y: u16| -> String {
+            let res: GetPointResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { x, y })
+                .unwrap();
+            res.point
+        };
+        let set = ExecuteMsg::Set { x: 0, y: 0, z: "aabbcc".to_string(), blend: None, proof: None };
+        let err = execute(&mut app, USER, set.clone()).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Canvas is still being initialized");
+
+        // The first chunk, then a slice crossing into the second one
+        let init = |offset: u32, data: String| ExecuteMsg::InitChunk { offset, data };
+        execute(&mut app, ADMIN, init(0, "111111".repeat(1024))).unwrap();
+        execute(&mut app, ADMIN, init(1020, "222222".repeat(8))).unwrap();
+        assert_eq!(point(&app, 19, 25), "111111");
+        assert_eq!(point(&app, 20, 25), "222222");
+        assert_eq!(point(&app, 27, 25), "222222");
+        assert_eq!(point(&app, 28, 25), "000000");
+
+        execute(&mut app, USER, init(0, "333333".to_string())).unwrap_err();
+        let err = execute(&mut app, ADMIN, init(1599, "333333".repeat(2))).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Index out of bounds");
+        execute(&mut app, ADMIN, init(0, "33333".to_string())).unwrap_err();
+
+        execute(&mut app, USER, ExecuteMsg::FinalizeInit {}).unwrap_err();
+        execute(&mut app, ADMIN, ExecuteMsg::FinalizeInit {}).unwrap();
+        let err = execute(&mut app, ADMIN, init(0, "333333".to_string())).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Canvas is already initialized");
+        execute(&mut app, USER, set).unwrap();
+        assert_eq!(point(&app, 0, 0), "aabbcc");
+    }
+
+    #[test]
+    fn test_blend() {
+        use crate::msg::{BlendMode, ExecuteMsg, GetPointResponse};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 1, "ffffff204080".to_string());
+        let user = MockApi::default().addr_make(USER);
+        let set = |app: &mut App, x: u16, z: &str, blend: Option<BlendMode>| {
+            let msg = ExecuteMsg::Set { x, y: 0, z: z.to_string(), blend, proof: None };
+            app.execute(user.clone(), cw_template_contract.call(msg).unwrap())
+        };
+
+        // Half transparent black over white, then a transparent paint that changes nothing
+        set(&mut app, 0, "00000080", Some(BlendMode::AlphaOver)).unwrap();
+        set(&mut app, 0, "ff000000", Some(BlendMode::AlphaOver)).unwrap();
+        // Multiplying by mid grey halves each channel
+        set(&mut app, 1, "808080", Some(BlendMode::Multiply)).unwrap();
+        let err = set(&mut app, 1, "1122334", None).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Invalid z value, must be a 6 (RGB) or 8 (RGBA) character hex string"
+        );
+        let point = |app: &App, x: u16| -> String {
+            let res: GetPointResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { x, y: 0 })
+                .unwrap();
+            res.point
+        };
+        assert_eq!(point(&app, 0), "7f7f7f");
+        assert_eq!(point(&app, 1), "102040");
+
+        // Replace drops the alpha, and RGB values are stored as sent
+        set(&mut app, 0, "11223344", Some(BlendMode::Replace)).unwrap();
+        set(&mut app, 1, "AABBCC", None).unwrap();
+        assert_eq!(point(&app, 0), "112233");
+        assert_eq!(point(&app, 1), "AABBCC");
+    }
+
+    #[test]
+    fn test_palette() {
+        use crate::msg::{
+            ExecuteMsg, GetPaletteProposalResponse, GetPaletteResponse, GetPointAtResponse,
+        };
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
+        let moderator = MockApi::default().addr_make("MODERATOR");
+        let colors = |colors: &[&str]| colors.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let msg = InstantiateMsg {
+            x_size: 2,
+            y_size: 2,
+            z_values: Some("ffffff000000ffffff0000FF".to_string()),
+            admin: None,
+            moderators: Some(vec![moderator.to_string()]),
+            rate_limit: None,
+            access_policy: None,
+            chunked_init: None,
+            palette: Some(colors(&["ffffff", "000000", "0000FF"])),
+            bit_depth: None,
+        };
+        let err = app
+            .instantiate_contract(
+                cw_template_id,
+                Addr::unchecked(ADMIN),
+                &InstantiateMsg { palette: Some(colors(&["ffffff", "FFFFFF"])), ..msg.clone() },
+                &[],
+                "test",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid palette: colors must be unique");
+        let err = app
+            .instantiate_contract(
+                cw_template_id,
+                Addr::unchecked(ADMIN),
+                &InstantiateMsg { z_values: Some("123456".repeat(4)), ..msg.clone() },
+                &[],
+                "test",
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Color is not in the palette");
+        let addr = app
+            .instantiate_contract(cw_template_id, Addr::unchecked(ADMIN), &msg, &[], "test", None)
+            .unwrap();
+        let cw_template_contract = CwTemplateContract(addr);
+        let user = MockApi::default().addr_make(USER);
+        let execute = |app: &mut App, sender: &Addr, msg: ExecuteMsg| {
+            app.execute(sender.clone(), cw_template_contract.call(msg).unwrap())
+        };
+        let grid = |app: &App| -> String {
+            let res: GetGridResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetGrid { start_after: None, limit: None })
+                .unwrap();
+            res.z_values
+        };
+        assert_eq!(grid(&app), "ffffff000000ffffff0000ff");
+
+        // Only palette colors can be painted, and blended colors must land on one
+        let set = |x: u16, z: &str| ExecuteMsg::Set { x, y: 0, z: z.to_string(), blend: None, proof: None };
+        let err = execute(&mut app, &user, set(0, "123456")).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Color is not in the palette");
+        let blend = ExecuteMsg::Set {
+            x: 0,
+            y: 0,
+            z: "00000080".to_string(),
+            blend: Some(crate::msg::BlendMode::AlphaOver),
+            proof: None,
+        };
+        let err = execute(&mut app, &user, blend).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Color is not in the palette");
+        execute(&mut app, &user, set(0, "0000FF")).unwrap();
+        assert_eq!(grid(&app), "0000ff000000ffffff0000ff");
+        let painted_at = app.block_info().height;
+        app.update_block(|block| block.height += 1);
+
+        // SetPalette recolors every point by index
+        let err = execute(&mut app, &moderator, ExecuteMsg::SetPalette { colors: colors(&["ff0000"]) })
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        let err = execute(&mut app, &Addr::unchecked(ADMIN), ExecuteMsg::SetPalette { colors: colors(&["ff0000"]) })
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid palette: cannot remove colors");
+        execute(
+            &mut app,
+            &Addr::unchecked(ADMIN),
+            ExecuteMsg::SetPalette { colors: colors(&["ffffff", "111111", "00ff00", "ff0000"]) },
+        )
+        .unwrap();
+        assert_eq!(grid(&app), "00ff00111111ffffff00ff00");
+        let past: GetPointAtResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetPointAt { x: 0, y: 0, height: painted_at },
+            )
+            .unwrap();
+        assert_eq!(past.point, "0000ff");
+
+        // Reverting after a palette change restores the indices of the time
+        execute(&mut app, &user, set(0, "ff0000")).unwrap();
+        let region = crate::msg::Region { x0: 0, y0: 0, width: 2, height: 1 };
+        let revert = ExecuteMsg::RevertRegion { region, height: Some(painted_at) };
+        execute(&mut app, &moderator, revert).unwrap();
+        assert_eq!(grid(&app), "00ff00111111ffffff00ff00");
+
+        // Moderators and the admin vote, and a majority of the two of them is both
+        let propose = ExecuteMsg::ProposePalette { colors: colors(&["ffffff", "222222", "00ff00", "ff0000"]) };
+        execute(&mut app, &user, propose.clone()).unwrap_err();
+        execute(&mut app, &moderator, propose.clone()).unwrap();
+        execute(&mut app, &moderator, propose).unwrap();
+        let err = execute(&mut app, &moderator, ExecuteMsg::VotePalette { id: 0 }).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Already voted");
+        let proposal: GetPaletteProposalResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPaletteProposal { id: 0 })
+            .unwrap();
+        assert_eq!((proposal.votes, proposal.voters, proposal.stale), (vec![moderator.clone()], 2, false));
+        execute(&mut app, &Addr::unchecked(ADMIN), ExecuteMsg::VotePalette { id: 0 }).unwrap();
+        assert_eq!(grid(&app), "00ff00222222ffffff00ff00");
+        let palette: GetPaletteResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPalette {})
+            .unwrap();
+        assert_eq!(palette.version, 2);
+        let err = execute(&mut app, &Addr::unchecked(ADMIN), ExecuteMsg::VotePalette { id: 1 }).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Palette proposal is stale, the palette changed since it was made"
+        );
+
+        // Votes of removed moderators no longer count
+        let former = MockApi::default().addr_make("FORMER");
+        let add = ExecuteMsg::AddModerator { address: former.to_string() };
+        execute(&mut app, &Addr::unchecked(ADMIN), add).unwrap();
+        let propose = ExecuteMsg::ProposePalette { colors: colors(&["ffffff", "333333", "00ff00", "ff0000"]) };
+        execute(&mut app, &former, propose).unwrap();
+        let remove = ExecuteMsg::RemoveModerator { address: former.to_string() };
+        execute(&mut app, &Addr::unchecked(ADMIN), remove).unwrap();
+        execute(&mut app, &moderator, ExecuteMsg::VotePalette { id: 2 }).unwrap();
+        assert_eq!(grid(&app), "00ff00222222ffffff00ff00");
+        execute(&mut app, &Addr::unchecked(ADMIN), ExecuteMsg::VotePalette { id: 2 }).unwrap();
+        assert_eq!(grid(&app), "00ff00333333ffffff00ff00");
+
+        // RGB canvases have no palette
+        let (mut app, cw_template_contract) = proper_instantiate(1, 1, String::new());
+        let err = app
+            .execute(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.call(ExecuteMsg::SetPalette { colors: colors(&["ffffff"]) }).unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Canvas is not in palette mode");
+    }
+
+    #[test]
+    fn test_bit_depth() {
+        use crate::msg::{ExecuteMsg, GetPaletteResponse, GetPointResponse};
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
+        let user = MockApi::default().addr_make(USER);
+        // 40x40 grid spans two chunks
+        let msg = InstantiateMsg {
+            x_size: 40,
+            y_size: 40,
+            z_values: None,
+            admin: None,
+            moderators: None,
+            rate_limit: None,
+            access_policy: None,
+            chunked_init: None,
+            palette: None,
+            bit_depth: Some(1),
+        };
+        let instantiate = |app: &mut App, msg: &InstantiateMsg| {
+            app.instantiate_contract(cw_template_id, Addr::unchecked(ADMIN), msg, &[], "test", None)
+        };
+        for (bit_depth, palette) in [(3, None), (24, Some(vec!["ffffff".to_string()]))] {
+            let err = instantiate(&mut app, &InstantiateMsg { bit_depth: Some(bit_depth), palette, ..msg.clone() })
+                .unwrap_err();
+            assert_eq!(
+                err.root_cause().to_string(),
+                "Invalid bit depth, must be 1, 2, 4 or 8, or 24 without a palette"
+            );
+        }
+        let palette = ["000000", "ff0000", "00ff00"].map(String::from).to_vec();
+        let err = instantiate(&mut app, &InstantiateMsg { palette: Some(palette), ..msg.clone() }).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid palette: must hold 1 to 2 colors");
+
+        // Monochrome points are black or white, packed eight to a byte
+        let cw_template_contract = CwTemplateContract(instantiate(&mut app, &msg).unwrap());
+        let set = |app: &mut App, x: u16, y: u16, z: &str| {
+            let msg = ExecuteMsg::Set { x, y, z: z.to_string(), blend: None, proof: None };
+            app.execute(user.clone(), cw_template_contract.call(msg).unwrap())
+        };
+        let point = |app: &App, x: u16, y: u16| -> String {
+            let res: GetPointResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { x, y })
+                .unwrap();
+            res.point
+        };
+        let err = set(&mut app, 0, 0, "808080").unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Color is not one of the gray levels of a 1 bit canvas"
+        );
+        for (x, y) in [(0, 0), (9, 0), (39, 25), (39, 39)] {
+            set(&mut app, x, y, "FFFFFF").unwrap();
+        }
+        let res: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetGrid { start_after: None, limit: None })
+            .unwrap();
+        let white: Vec<usize> = res
+            .z_values
+            .as_bytes()
+            .chunks(6)
+            .enumerate()
+            .filter(|(_, z)| *z == b"ffffff")
+            .map(|(idx, _)| idx)
+            .collect();
+        assert_eq!(white, vec![0, 9, 1039, 1599]);
+        assert_eq!(point(&app, 1, 0), "000000");
+
+        // 4 bit grayscale takes 16 evenly spaced levels, and blends that land on one
+        let z_values = "000000111111eeeeeeffffff".to_string();
+        let msg = InstantiateMsg { x_size: 2, y_size: 2, z_values: Some(z_values.clone()), bit_depth: Some(4), ..msg };
+        let cw_template_contract = CwTemplateContract(instantiate(&mut app, &msg).unwrap());
+        let res: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetGrid { start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(res.z_values, z_values);
+        let blend = ExecuteMsg::Set {
+            x: 1,
+            y: 1,
+            z: "00000088".to_string(),
+            blend: Some(crate::msg::BlendMode::AlphaOver),
+            proof: None,
+        };
+        app.execute(user.clone(), cw_template_contract.call(blend).unwrap()).unwrap();
+        let res: GetPointResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { x: 1, y: 1 })
+            .unwrap();
+        assert_eq!(res.point, "777777");
+        let res: GetPaletteResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPalette {})
+            .unwrap();
+        assert_eq!((res.colors, res.bit_depth), (None, 4));
+        let msg = InstantiateMsg { z_values: Some("123456".repeat(4)), ..msg };
+        let err = instantiate(&mut app, &msg).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Color is not one of the gray levels of a 4 bit canvas"
+        );
+    }
 }
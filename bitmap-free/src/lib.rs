@@ -1,8 +1,18 @@
+pub mod access;
+pub mod blend;
+pub mod changes;
 pub mod contract;
+pub mod depth;
 mod error;
+pub mod grid;
 pub mod helpers;
+pub mod init;
 pub mod integration_tests;
+pub mod moderation;
 pub mod msg;
+pub mod palette;
+pub mod render;
+pub mod rate_limit;
 pub mod state;
 
 pub use crate::error::ContractError;
@@ -0,0 +1,333 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+use crate::changes;
+use crate::error::ContractError;
+use crate::grid;
+use crate::msg::{
+    GetModerationLogResponse, GetModeratorsResponse, GetProtectedRegionsResponse, IsBannedResponse,
+    ModerationAction, ModerationLogEntry, ProtectedRegion, Region,
+};
+use crate::state::{
+    ModerationEntry, BANNED, MODERATION_LOG, MODERATORS, NEXT_MODERATION, NEXT_PROTECTED_REGION,
+    PAINTERS, PROTECTED_REGIONS, STATE,
+};
+
+/// Maximum number of points restored by a single RevertRegion
+pub const MAX_REVERT_POINTS: u32 = 1024;
+/// Maximum number of protected regions, which every Set checks
+pub const MAX_PROTECTED_REGIONS: usize = 32;
+pub const DEFAULT_LOG_LIMIT: u32 = 30;
+pub const MAX_LOG_LIMIT: u32 = 100;
+
+/// The admin is always a moderator
+pub fn ensure_moderator(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if STATE.load(storage)?.admin == *sender || MODERATORS.has(storage, sender) {
+        Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+/// Rejects banned painters and points inside a protected region
+pub fn ensure_can_paint(
+    storage: &dyn Storage,
+    sender: &Addr,
+    x: u16,
+    y: u16,
+) -> Result<(), ContractError> {
+    if BANNED.has(storage, sender) {
+        return Err(ContractError::Banned {});
+    }
+    for region in PROTECTED_REGIONS.range(storage, None, None, Order::Ascending) {
+        if region?.1.contains(x, y) {
+            return Err(ContractError::ProtectedRegion {});
+        }
+    }
+    Ok(())
+}
+
+fn log_action(
+    storage: &mut dyn Storage,
+    env: &Env,
+    moderator: &Addr,
+    action: ModerationAction,
+) -> StdResult<u64> {
+    let id = NEXT_MODERATION.may_load(storage)?.unwrap_or_default();
+    let entry = ModerationEntry {
+        height: env.block.height,
+        moderator: moderator.clone(),
+        action,
+    };
+    MODERATION_LOG.save(storage, id, &entry)?;
+    NEXT_MODERATION.save(storage, &(id + 1))?;
+    Ok(id)
+}
+
+fn check_region(storage: &dyn Storage, region: &Region, max_points: u32) -> Result<(), ContractError> {
+    let state = STATE.load(storage)?;
+    if region.x0 as u32 + region.width as u32 > state.x_size as u32
+        || region.y0 as u32 + region.height as u32 > state.y_size as u32
+    {
+        return Err(ContractError::IndexOutOfBounds {});
+    }
+    if (region.width as u32) * (region.height as u32) > max_points {
+        return Err(ContractError::RegionTooLarge {});
+    }
+    Ok(())
+}
+
+pub fn add_moderator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    MODERATORS.save(deps.storage, &address, &Empty {})?;
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::AddModerator { address: address.clone() },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "add_moderator")
+        .add_attribute("address", address)
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn remove_moderator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    MODERATORS.remove(deps.storage, &address);
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::RemoveModerator { address: address.clone() },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "remove_moderator")
+        .add_attribute("address", address)
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn revert_region(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    region: Region,
+    height: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_moderator(deps.storage, &info.sender)?;
+    check_region(deps.storage, &region, MAX_REVERT_POINTS)?;
+    let state = STATE.load(deps.storage)?;
+    let grid_len = grid::grid_len(state.x_size, state.y_size);
+    let blank = grid::blank_point(deps.storage)?;
+
+    // Current chunks being rewritten, and the chunks they are restored from
+    let mut chunks: BTreeMap<u32, String> = BTreeMap::new();
+    let mut sources: BTreeMap<u32, String> = BTreeMap::new();
+    for y in region.y0..region.y0 + region.height {
+        for x in region.x0..region.x0 + region.width {
+            let idx = (y as u32) * (state.x_size as u32) + (x as u32);
+            let chunk = idx / grid::CHUNK_SIZE;
+            let start = (idx % grid::CHUNK_SIZE) as usize * grid::Z_LEN;
+            let z = match height {
+                Some(height) => {
+                    let source = match sources.entry(chunk) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => {
+                            entry.insert(grid::load_stored_chunk_at(deps.storage, grid_len, chunk, height)?)
+                        }
+                    };
+                    let z = source[start..start + grid::Z_LEN].to_string();
+                    match PAINTERS.may_load_at_height(deps.storage, idx, height.saturating_add(1))? {
+                        Some(painter) => PAINTERS.save(deps.storage, idx, &painter, env.block.height)?,
+                        None => PAINTERS.remove(deps.storage, idx, env.block.height)?,
+                    }
+                    z
+                }
+                None => {
+                    PAINTERS.remove(deps.storage, idx, env.block.height)?;
+                    blank.clone()
+                }
+            };
+            let current = match chunks.entry(chunk) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(grid::load_chunk(deps.storage, grid_len, chunk)?),
+            };
+            current.replace_range(start..start + grid::Z_LEN, &z);
+            changes::record_change(deps.storage, &env, x, y, &z, &info.sender)?;
+        }
+    }
+    for (chunk, z_values) in chunks {
+        grid::save_chunk(deps.storage, chunk, &z_values, env.block.height)?;
+    }
+
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::RevertRegion { region, height },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "revert_region")
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn ban_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_moderator(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    if ensure_moderator(deps.storage, &address).is_ok() {
+        return Err(ContractError::CannotBanModerator {});
+    }
+    BANNED.save(deps.storage, &address, &Empty {})?;
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::BanAddress { address: address.clone() },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "ban_address")
+        .add_attribute("address", address)
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn unban_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_moderator(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    BANNED.remove(deps.storage, &address);
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::UnbanAddress { address: address.clone() },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "unban_address")
+        .add_attribute("address", address)
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn protect_region(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    region: Region,
+) -> Result<Response, ContractError> {
+    ensure_moderator(deps.storage, &info.sender)?;
+    check_region(deps.storage, &region, u32::MAX)?;
+    let num_regions = PROTECTED_REGIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if num_regions >= MAX_PROTECTED_REGIONS {
+        return Err(ContractError::TooManyProtectedRegions {});
+    }
+    let region_id = NEXT_PROTECTED_REGION.may_load(deps.storage)?.unwrap_or_default();
+    PROTECTED_REGIONS.save(deps.storage, region_id, &region)?;
+    NEXT_PROTECTED_REGION.save(deps.storage, &(region_id + 1))?;
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::ProtectRegion { id: region_id, region },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "protect_region")
+        .add_attribute("region_id", region_id.to_string())
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn unprotect_region(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    region_id: u64,
+) -> Result<Response, ContractError> {
+    ensure_moderator(deps.storage, &info.sender)?;
+    PROTECTED_REGIONS.remove(deps.storage, region_id);
+    let id = log_action(
+        deps.storage,
+        &env,
+        &info.sender,
+        ModerationAction::UnprotectRegion { id: region_id },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "unprotect_region")
+        .add_attribute("region_id", region_id.to_string())
+        .add_attribute("log_id", id.to_string()))
+}
+
+pub fn get_moderators(deps: Deps) -> StdResult<GetModeratorsResponse> {
+    let admin = STATE.load(deps.storage)?.admin;
+    let moderators = MODERATORS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetModeratorsResponse { admin, moderators })
+}
+
+pub fn is_banned(deps: Deps, address: String) -> StdResult<IsBannedResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(IsBannedResponse {
+        banned: BANNED.has(deps.storage, &address),
+    })
+}
+
+pub fn get_protected_regions(deps: Deps) -> StdResult<GetProtectedRegionsResponse> {
+    let regions = PROTECTED_REGIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (id, region) = item?;
+            Ok(ProtectedRegion { id, region })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetProtectedRegionsResponse { regions })
+}
+
+pub fn get_moderation_log(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<GetModerationLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).min(MAX_LOG_LIMIT) as usize;
+    let entries = MODERATION_LOG
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (id, entry) = item?;
+            Ok(ModerationLogEntry {
+                id,
+                height: entry.height,
+                moderator: entry.moderator,
+                action: entry.action,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetModerationLogResponse { entries })
+}
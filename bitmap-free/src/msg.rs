@@ -1,16 +1,173 @@
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Uint128};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub x_size: u8,
-    pub y_size: u8,
+    pub x_size: u16,
+    pub y_size: u16,
     pub z_values: Option<String>,
+    /// Defaults to the instantiating address
+    pub admin: Option<String>,
+    pub moderators: Option<Vec<String>>,
+    pub rate_limit: Option<RateLimit>,
+    /// Defaults to Open
+    pub access_policy: Option<AccessPolicy>,
+    /// Keeps the canvas closed until the creator sends FinalizeInit, so large
+    /// starting images can be uploaded with InitChunk
+    pub chunked_init: Option<bool>,
+    /// Stores each point as an index into these colors instead of as hex.
+    /// Points can then only be painted with palette colors.
+    pub palette: Option<Vec<String>>,
+    /// Bits stored per point: 1, 2, 4 or 8 bit grayscale, or 24 bit RGB by default.
+    /// With a palette, the size of the indices (8 by default), which limits the
+    /// palette to 2^bit_depth colors.
+    pub bit_depth: Option<u8>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    Set { x: u8, y: u8, z: String },
+    /// Set paints the point (x, y). `proof` is only needed under a MerkleAllowlist policy.
+    /// `z` is RGB, or RGBA with 8 characters, and `blend` (Replace by default) decides
+    /// how it mixes with the point's current color.
+    Set {
+        x: u16,
+        y: u16,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+    },
+
+    /// SetAccessPolicy replaces the access policy (admin only)
+    SetAccessPolicy { policy: AccessPolicy },
+
+    /// UpdateAllowlist adds and removes addresses under an Allowlist policy (admin only)
+    UpdateAllowlist { add: Vec<String>, remove: Vec<String> },
+
+    /// InitChunk writes consecutive z_values starting at grid index `offset`
+    /// (creator only, before FinalizeInit)
+    InitChunk { offset: u32, data: String },
+
+    /// FinalizeInit opens the canvas for Set (creator only)
+    FinalizeInit {},
+
+    /// AddModerator grants moderator rights (admin only)
+    AddModerator { address: String },
+
+    /// RemoveModerator revokes moderator rights (admin only)
+    RemoveModerator { address: String },
+
+    /// RevertRegion restores a rectangle to its content at the end of block `height`,
+    /// or to "000000" (the first palette color in palette mode) if no height is given.
+    /// In palette mode the points get back their palette index, shown in the current colors.
+    RevertRegion { region: Region, height: Option<u64> },
+
+    /// BanAddress blocks an address from setting points
+    BanAddress { address: String },
+
+    /// UnbanAddress lifts a ban
+    UnbanAddress { address: String },
+
+    /// ProtectRegion freezes a rectangle so that Set cannot change it
+    ProtectRegion { region: Region },
+
+    /// UnprotectRegion removes a protected region by id
+    UnprotectRegion { id: u64 },
+
+    /// UpdateRateLimit replaces the cooldown and quota settings (admin only)
+    UpdateRateLimit { rate_limit: Option<RateLimit> },
+
+    /// SetPalette replaces the palette of a palette mode canvas (admin only). Points keep
+    /// their indices, so a new color at an index recolors every point using it.
+    /// The palette can grow but not shrink.
+    SetPalette { colors: Vec<String> },
+
+    /// ProposePalette puts a new palette to a vote of the admin and moderators
+    /// (moderators only), counting the proposer's vote
+    ProposePalette { colors: Vec<String> },
+
+    /// VotePalette votes for a palette proposal (moderators only). The palette is
+    /// replaced once more than half of the current admin and moderators voted for it.
+    VotePalette { id: u64 },
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub enum Interval {
+    Blocks(u64),
+    Seconds(u64),
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct Quota {
+    pub epoch: Interval,
+    pub max_points: u32,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct RateLimit {
+    /// Time an address must wait between two points
+    pub cooldown: Option<Interval>,
+    /// Maximum number of points an address can set per epoch
+    pub quota: Option<Quota>,
+}
+
+#[cw_serde]
+pub struct Region {
+    pub x0: u16,
+    pub y0: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Region {
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x >= self.x0
+            && y >= self.y0
+            && (x as u32) < self.x0 as u32 + self.width as u32
+            && (y as u32) < self.y0 as u32 + self.height as u32
+    }
+}
+
+#[cw_serde]
+pub enum ModerationAction {
+    AddModerator { address: Addr },
+    RemoveModerator { address: Addr },
+    RevertRegion { region: Region, height: Option<u64> },
+    BanAddress { address: Addr },
+    UnbanAddress { address: Addr },
+    ProtectRegion { id: u64, region: Region },
+    UnprotectRegion { id: u64 },
+}
+
+/// How a Set mixes with the current color of the point
+#[cw_serde]
+#[derive(Default)]
+pub enum BlendMode {
+    /// Overwrites the point, ignoring alpha
+    #[default]
+    Replace,
+    /// Paints over the point, weighted by the alpha of z
+    AlphaOver,
+    /// Multiplies the point by z, weighted by the alpha of z
+    Multiply,
+}
+
+/// Who can set points
+#[cw_serde]
+pub enum AccessPolicy {
+    /// Anyone
+    Open {},
+    /// Only the listed addresses, managed by the admin
+    Allowlist(Vec<Addr>),
+    /// Addresses holding at least `min` of a native denom
+    HoldsNativeBalance { denom: String, min: Uint128 },
+    /// Addresses owning at least one token of a cw721 collection
+    HoldsCw721 { collection: String },
+    /// Addresses whose sha256 hash is a leaf of the merkle tree with this hex-encoded root
+    MerkleAllowlist { root: String },
 }
 
 #[cw_serde]
@@ -18,11 +175,105 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     /// GetPoint returns the point (z) at (x, y)
     #[returns(GetPointResponse)]
-    GetPoint { x: u8, y: u8 },
+    GetPoint { x: u16, y: u16 },
     
-    /// GetGrid returns the entire grid as a string
+    /// GetGrid returns the grid as a string, one page of chunks at a time.
+    /// Grids up to 255x255 fit in a single page.
+    #[returns(GetGridResponse)]
+    GetGrid {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+
+    /// GetGridAt returns a page of the grid as it was at the end of block `height`
     #[returns(GetGridResponse)]
-    GetGrid {},
+    GetGridAt {
+        height: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+
+    /// GetPointAt returns the point (z) at (x, y) and its painter at the end of block `height`
+    #[returns(GetPointAtResponse)]
+    GetPointAt { x: u16, y: u16, height: u64 },
+
+    /// GetRegion returns a rectangle of the grid, row by row
+    #[returns(GetRegionResponse)]
+    GetRegion {
+        x0: u16,
+        y0: u16,
+        width: u16,
+        height: u16,
+    },
+
+    /// GetRow returns a full row of the grid
+    #[returns(GetRowResponse)]
+    GetRow { y: u16 },
+
+    /// GetPoints returns the points (z) at each (x, y), in the same order
+    #[returns(GetPointsResponse)]
+    GetPoints { coords: Vec<(u16, u16)> },
+
+    /// RenderSvg returns the grid, or a region of it, as an SVG document
+    /// where each point is `scale` pixels wide
+    #[returns(RenderSvgResponse)]
+    RenderSvg {
+        scale: Option<u32>,
+        region: Option<Region>,
+    },
+
+    /// RenderImage returns the grid, or a region of it, as a BMP or PNG image
+    /// along with a `data:` URI that can be used directly as an image source
+    #[returns(RenderImageResponse)]
+    RenderImage {
+        format: ImageFormat,
+        scale: Option<u32>,
+        region: Option<Region>,
+    },
+
+    /// GetAccessPolicy returns who can set points
+    #[returns(AccessPolicy)]
+    GetAccessPolicy {},
+
+    /// GetChangesSince returns the points painted after a block height, oldest first
+    #[returns(GetChangesSinceResponse)]
+    GetChangesSince {
+        height: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// GetModerators returns the admin and the moderators
+    #[returns(GetModeratorsResponse)]
+    GetModerators {},
+
+    /// IsBanned returns whether an address is banned from setting points
+    #[returns(IsBannedResponse)]
+    IsBanned { address: String },
+
+    /// GetProtectedRegions returns the rectangles that Set cannot change
+    #[returns(GetProtectedRegionsResponse)]
+    GetProtectedRegions {},
+
+    /// GetCooldown returns how long an address must wait before setting a point
+    #[returns(GetCooldownResponse)]
+    GetCooldown { address: String },
+
+    /// GetModerationLog returns the moderation actions, oldest first
+    #[returns(GetModerationLogResponse)]
+    GetModerationLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// GetPalette returns the palette, None if the canvas is not in palette mode,
+    /// and the bit depth of the canvas
+    #[returns(GetPaletteResponse)]
+    GetPalette {},
+
+    /// GetPaletteProposal returns an open palette proposal and its votes
+    #[returns(GetPaletteProposalResponse)]
+    GetPaletteProposal { id: u64 },
 }
 
 #[cw_serde]
@@ -32,7 +283,139 @@ pub struct GetPointResponse {
 
 #[cw_serde]
 pub struct GetGridResponse {
-    pub x_size: u8,
-    pub y_size: u8,
+    pub x_size: u16,
+    pub y_size: u16,
+    /// Grid index of the first point in z_values
+    pub offset: u32,
     pub z_values: String,
-}
\ No newline at end of file
+    /// Chunk to pass as start_after to fetch the next page, None on the last page
+    pub next_start_after: Option<u32>,
+}
+
+#[cw_serde]
+pub struct GetRegionResponse {
+    pub x0: u16,
+    pub y0: u16,
+    pub width: u16,
+    pub height: u16,
+    pub z_values: String,
+}
+
+#[cw_serde]
+pub struct GetRowResponse {
+    pub y: u16,
+    pub z_values: String,
+}
+
+#[cw_serde]
+pub struct GetPointsResponse {
+    pub points: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ChangeInfo {
+    pub seq: u64,
+    pub height: u64,
+    pub x: u16,
+    pub y: u16,
+    pub z: String,
+    pub painter: Addr,
+}
+
+#[cw_serde]
+pub struct GetChangesSinceResponse {
+    pub changes: Vec<ChangeInfo>,
+    /// True if older changes after the height were dropped from the log and the grid must be refetched
+    pub truncated: bool,
+}
+
+#[cw_serde]
+pub struct GetPointAtResponse {
+    pub point: String,
+    /// None if the point had not been painted yet
+    pub painter: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct GetModeratorsResponse {
+    pub admin: Addr,
+    pub moderators: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct IsBannedResponse {
+    pub banned: bool,
+}
+
+#[cw_serde]
+pub struct ProtectedRegion {
+    pub id: u64,
+    pub region: Region,
+}
+
+#[cw_serde]
+pub struct GetProtectedRegionsResponse {
+    pub regions: Vec<ProtectedRegion>,
+}
+
+#[cw_serde]
+pub struct ModerationLogEntry {
+    pub id: u64,
+    pub height: u64,
+    pub moderator: Addr,
+    pub action: ModerationAction,
+}
+
+#[cw_serde]
+pub struct GetModerationLogResponse {
+    pub entries: Vec<ModerationLogEntry>,
+}
+
+#[cw_serde]
+pub struct GetCooldownResponse {
+    /// Blocks or seconds left before the address can set a point, None if there is no cooldown
+    pub remaining: Option<Interval>,
+    /// Points the address can still set in the current epoch, None if there is no quota
+    pub points_left: Option<u32>,
+    /// Blocks or seconds until the current epoch ends, None if there is no quota
+    pub epoch_remaining: Option<Interval>,
+}
+
+#[cw_serde]
+pub struct RenderSvgResponse {
+    pub svg: String,
+}
+
+#[cw_serde]
+pub enum ImageFormat {
+    Bmp,
+    Png,
+}
+
+#[cw_serde]
+pub struct RenderImageResponse {
+    pub image: Binary,
+    pub data_uri: String,
+}
+
+#[cw_serde]
+pub struct GetPaletteResponse {
+    pub colors: Option<Vec<String>>,
+    /// Number of times the palette was replaced
+    pub version: u64,
+    /// Bits stored per point, 24 for hex RGB
+    pub bit_depth: u8,
+}
+
+#[cw_serde]
+pub struct GetPaletteProposalResponse {
+    pub id: u64,
+    pub colors: Vec<String>,
+    pub proposer: Addr,
+    /// Addresses that voted for the proposal; only those still the admin or a moderator count
+    pub votes: Vec<Addr>,
+    /// Number of addresses that can vote, the admin and the moderators
+    pub voters: u32,
+    /// True if the palette changed since the proposal was made, which closes it
+    pub stale: bool,
+}
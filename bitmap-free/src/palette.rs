@@ -0,0 +1,182 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage};
+
+use crate::depth;
+use crate::error::ContractError;
+use crate::grid;
+use crate::moderation;
+use crate::msg::{GetPaletteProposalResponse, GetPaletteResponse};
+use crate::state::{
+    PaletteProposal, MODERATORS, NEXT_PALETTE_PROPOSAL, PALETTE, PALETTE_PROPOSALS, PALETTE_VERSION, STATE,
+};
+
+fn invalid(reason: &str) -> ContractError {
+    ContractError::InvalidPalette {
+        reason: reason.to_string(),
+    }
+}
+
+/// Lowercases and checks a palette, which must fit in indices of bit_depth bits.
+/// A replacement cannot be shorter than `current`, so every stored index keeps
+/// pointing at a color.
+pub fn validate(
+    colors: Vec<String>,
+    current: Option<&[String]>,
+    bit_depth: u8,
+) -> Result<Vec<String>, ContractError> {
+    let max_colors = depth::max_colors(bit_depth);
+    if colors.is_empty() || colors.len() > max_colors {
+        return Err(ContractError::InvalidPalette {
+            reason: format!("must hold 1 to {max_colors} colors"),
+        });
+    }
+    let colors: Vec<String> = colors.into_iter().map(|color| color.to_ascii_lowercase()).collect();
+    if colors.iter().any(|color| grid::parse_color(color).is_none()) {
+        return Err(invalid("colors must be 6 character hex strings"));
+    }
+    if (1..colors.len()).any(|i| colors[..i].contains(&colors[i])) {
+        return Err(invalid("colors must be unique"));
+    }
+    if current.is_some_and(|current| colors.len() < current.len()) {
+        return Err(invalid("cannot remove colors"));
+    }
+    Ok(colors)
+}
+
+fn apply(storage: &mut dyn Storage, env: &Env, colors: &[String]) -> StdResult<u64> {
+    PALETTE.save(storage, &colors.to_vec(), env.block.height)?;
+    let version = PALETTE_VERSION.may_load(storage)?.unwrap_or_default() + 1;
+    PALETTE_VERSION.save(storage, &version)?;
+    Ok(version)
+}
+
+fn load_palette(storage: &dyn Storage) -> Result<Vec<String>, ContractError> {
+    PALETTE.may_load(storage)?.ok_or(ContractError::NotPaletteMode {})
+}
+
+/// Voters are the moderators and the admin
+fn num_voters(storage: &dyn Storage, admin: &Addr) -> StdResult<u32> {
+    let moderators = MODERATORS.keys(storage, None, None, Order::Ascending).count() as u32;
+    Ok(moderators + u32::from(!MODERATORS.has(storage, admin)))
+}
+
+/// Votes of addresses that are still the admin or a moderator
+fn num_valid_votes(storage: &dyn Storage, admin: &Addr, votes: &[Addr]) -> u32 {
+    votes
+        .iter()
+        .filter(|voter| *voter == admin || MODERATORS.has(storage, voter))
+        .count() as u32
+}
+
+/// Replaces the palette (admin only)
+pub fn set_palette(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    colors: Vec<String>,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let current = load_palette(deps.storage)?;
+    let colors = validate(colors, Some(&current), depth::bit_depth(deps.storage)?)?;
+    let version = apply(deps.storage, &env, &colors)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_palette")
+        .add_attribute("colors", colors.len().to_string())
+        .add_attribute("version", version.to_string()))
+}
+
+/// Opens a vote on a new palette (moderators only), counting the proposer's vote
+pub fn propose_palette(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    colors: Vec<String>,
+) -> Result<Response, ContractError> {
+    moderation::ensure_moderator(deps.storage, &info.sender)?;
+    let current = load_palette(deps.storage)?;
+    let colors = validate(colors, Some(&current), depth::bit_depth(deps.storage)?)?;
+    let id = NEXT_PALETTE_PROPOSAL.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_PALETTE_PROPOSAL.save(deps.storage, &(id + 1))?;
+    let proposal = PaletteProposal {
+        colors,
+        proposer: info.sender.clone(),
+        version: PALETTE_VERSION.may_load(deps.storage)?.unwrap_or_default(),
+        votes: vec![],
+    };
+    let res = Response::new()
+        .add_attribute("action", "propose_palette")
+        .add_attribute("proposal_id", id.to_string());
+    record_vote(deps, env, info, id, proposal, res)
+}
+
+/// Votes for a palette proposal (moderators only). The palette is replaced as soon
+/// as more than half of the voters agree; votes of removed moderators do not count.
+pub fn vote_palette(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    moderation::ensure_moderator(deps.storage, &info.sender)?;
+    let proposal = PALETTE_PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::ProposalNotFound {})?;
+    if proposal.version != PALETTE_VERSION.may_load(deps.storage)?.unwrap_or_default() {
+        return Err(ContractError::StaleProposal {});
+    }
+    if proposal.votes.contains(&info.sender) {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    let res = Response::new()
+        .add_attribute("action", "vote_palette")
+        .add_attribute("proposal_id", id.to_string());
+    record_vote(deps, env, info, id, proposal, res)
+}
+
+fn record_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    mut proposal: PaletteProposal,
+    res: Response,
+) -> Result<Response, ContractError> {
+    proposal.votes.push(info.sender);
+    let admin = STATE.load(deps.storage)?.admin;
+    let votes = num_valid_votes(deps.storage, &admin, &proposal.votes);
+    let res = res.add_attribute("votes", votes.to_string());
+    if votes * 2 <= num_voters(deps.storage, &admin)? {
+        PALETTE_PROPOSALS.save(deps.storage, id, &proposal)?;
+        return Ok(res);
+    }
+    PALETTE_PROPOSALS.remove(deps.storage, id);
+    let version = apply(deps.storage, &env, &proposal.colors)?;
+    Ok(res
+        .add_attribute("applied", "true")
+        .add_attribute("version", version.to_string()))
+}
+
+pub fn get_palette(deps: Deps) -> StdResult<GetPaletteResponse> {
+    Ok(GetPaletteResponse {
+        colors: PALETTE.may_load(deps.storage)?,
+        version: PALETTE_VERSION.may_load(deps.storage)?.unwrap_or_default(),
+        bit_depth: depth::bit_depth(deps.storage)?,
+    })
+}
+
+pub fn get_palette_proposal(deps: Deps, id: u64) -> StdResult<GetPaletteProposalResponse> {
+    let proposal = PALETTE_PROPOSALS
+        .may_load(deps.storage, id)?
+        .ok_or_else(|| StdError::generic_err("Palette proposal not found"))?;
+    let admin = STATE.load(deps.storage)?.admin;
+    let version = PALETTE_VERSION.may_load(deps.storage)?.unwrap_or_default();
+    Ok(GetPaletteProposalResponse {
+        id,
+        colors: proposal.colors,
+        proposer: proposal.proposer,
+        votes: proposal.votes,
+        voters: num_voters(deps.storage, &admin)?,
+        stale: proposal.version != version,
+    })
+}
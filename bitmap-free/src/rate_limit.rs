@@ -0,0 +1,120 @@
+use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+
+use crate::error::ContractError;
+use crate::msg::{GetCooldownResponse, Interval, RateLimit};
+use crate::state::{EPOCH_USAGE, LAST_PAINT, STATE};
+
+/// Current block height or time in seconds, matching the unit of the interval
+fn now(interval: &Interval, env: &Env) -> u64 {
+    match interval {
+        Interval::Blocks(_) => env.block.height,
+        Interval::Seconds(_) => env.block.time.seconds(),
+    }
+}
+
+fn length(interval: &Interval) -> u64 {
+    match interval {
+        Interval::Blocks(n) | Interval::Seconds(n) => *n,
+    }
+}
+
+fn with_length(interval: &Interval, n: u64) -> Interval {
+    match interval {
+        Interval::Blocks(_) => Interval::Blocks(n),
+        Interval::Seconds(_) => Interval::Seconds(n),
+    }
+}
+
+/// Blocks or seconds left in the cooldown of an address
+fn cooldown_remaining(
+    storage: &dyn Storage,
+    env: &Env,
+    cooldown: &Interval,
+    sender: &Addr,
+) -> StdResult<u64> {
+    let now = now(cooldown, env);
+    match LAST_PAINT.may_load(storage, sender)? {
+        // A last paint in the future was recorded in the other unit, so the cooldown has changed and expired
+        Some(last) if last <= now => Ok(last.saturating_add(length(cooldown)).saturating_sub(now)),
+        _ => Ok(0),
+    }
+}
+
+/// Points set by an address in the current epoch
+fn epoch_points(storage: &dyn Storage, env: &Env, epoch: &Interval, sender: &Addr) -> StdResult<u32> {
+    let current = now(epoch, env) / length(epoch).max(1);
+    match EPOCH_USAGE.may_load(storage, sender)? {
+        Some((index, points)) if index == current => Ok(points),
+        _ => Ok(0),
+    }
+}
+
+/// Rejects the sender if its cooldown is active or its epoch quota is used up,
+/// otherwise records the new point
+pub fn check_and_record(storage: &mut dyn Storage, env: &Env, sender: &Addr) -> Result<(), ContractError> {
+    let Some(rate_limit) = STATE.load(storage)?.rate_limit else {
+        return Ok(());
+    };
+    if let Some(cooldown) = rate_limit.cooldown {
+        let remaining = cooldown_remaining(storage, env, &cooldown, sender)?;
+        if remaining > 0 {
+            return Err(ContractError::CooldownActive { remaining });
+        }
+        LAST_PAINT.save(storage, sender, &now(&cooldown, env))?;
+    }
+    if let Some(quota) = rate_limit.quota {
+        let points = epoch_points(storage, env, &quota.epoch, sender)?;
+        if points >= quota.max_points {
+            return Err(ContractError::QuotaExceeded {});
+        }
+        let current = now(&quota.epoch, env) / length(&quota.epoch).max(1);
+        EPOCH_USAGE.save(storage, sender, &(current, points + 1))?;
+    }
+    Ok(())
+}
+
+pub fn update_rate_limit(
+    deps: DepsMut,
+    info: MessageInfo,
+    rate_limit: Option<RateLimit>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    state.rate_limit = rate_limit;
+    STATE.save(deps.storage, &state)?;
+    Ok(Response::new().add_attribute("action", "update_rate_limit"))
+}
+
+pub fn get_cooldown(deps: Deps, env: Env, address: String) -> StdResult<GetCooldownResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let rate_limit = STATE.load(deps.storage)?.rate_limit.unwrap_or(RateLimit {
+        cooldown: None,
+        quota: None,
+    });
+    let remaining = match rate_limit.cooldown {
+        Some(cooldown) => {
+            let remaining = cooldown_remaining(deps.storage, &env, &cooldown, &address)?;
+            Some(with_length(&cooldown, remaining))
+        }
+        None => None,
+    };
+    let (points_left, epoch_remaining) = match rate_limit.quota {
+        Some(quota) => {
+            let points = epoch_points(deps.storage, &env, &quota.epoch, &address)?;
+            let length = length(&quota.epoch).max(1);
+            let elapsed = now(&quota.epoch, &env) % length;
+            (
+                Some(quota.max_points.saturating_sub(points)),
+                Some(with_length(&quota.epoch, length - elapsed)),
+            )
+        }
+        None => (None, None),
+    };
+    Ok(GetCooldownResponse {
+        remaining,
+        points_left,
+        epoch_remaining,
+    })
+}
@@ -0,0 +1,264 @@
+use std::fmt::Write;
+
+use cosmwasm_std::{StdError, StdResult};
+
+use crate::grid;
+
+/// Largest number of pixels per point accepted by RenderSvg
+pub const MAX_SCALE: u32 = 64;
+/// Largest number of rects in a RenderSvg document, about 1 MB of output
+pub const MAX_SVG_RECTS: u32 = 16384;
+
+/// Renders a width x height block of z_values as an SVG document. Runs of equal
+/// color on a row are merged into a single rect to keep the output small, and
+/// documents with more than MAX_SVG_RECTS rects are rejected.
+pub fn svg(z_values: &str, width: u16, height: u16, scale: u32) -> StdResult<String> {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+        width as u32 * scale,
+        height as u32 * scale,
+        width,
+        height,
+    );
+    let mut rects = 0;
+    for y in 0..height as usize {
+        let row = &z_values[y * width as usize * grid::Z_LEN..(y + 1) * width as usize * grid::Z_LEN];
+        let mut x = 0;
+        while x < width as usize {
+            let color = color_at(row, x);
+            let mut run = 1;
+            while x + run < width as usize && color_at(row, x + run) == color {
+                run += 1;
+            }
+            rects += 1;
+            if rects > MAX_SVG_RECTS {
+                return Err(StdError::generic_err("Image too large"));
+            }
+            let _ = write!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="1" fill="#{:02x}{:02x}{:02x}"/>"##,
+                x, y, run, color[0], color[1], color[2],
+            );
+            x += run;
+        }
+    }
+    svg.push_str("</svg>");
+    Ok(svg)
+}
+
+// Points that are not valid hex render as black
+fn color_at(row: &str, x: usize) -> [u8; 3] {
+    grid::parse_color(&row[x * grid::Z_LEN..(x + 1) * grid::Z_LEN]).unwrap_or_default()
+}
+
+/// Largest image, in pixels, accepted by RenderImage
+pub const MAX_IMAGE_PIXELS: u32 = 512 * 512;
+
+/// Expands a width x height block of z_values into rows of RGB pixels,
+/// each point becoming a scale x scale square
+pub fn rgb_rows(z_values: &str, width: u16, height: u16, scale: u32) -> Vec<Vec<u8>> {
+    let mut rows = Vec::with_capacity(height as usize * scale as usize);
+    for y in 0..height as usize {
+        let row = &z_values[y * width as usize * grid::Z_LEN..(y + 1) * width as usize * grid::Z_LEN];
+        let mut pixels = Vec::with_capacity(width as usize * scale as usize * 3);
+        for x in 0..width as usize {
+            let color = color_at(row, x);
+            for _ in 0..scale {
+                pixels.extend_from_slice(&color);
+            }
+        }
+        for _ in 0..scale {
+            rows.push(pixels.clone());
+        }
+    }
+    rows
+}
+
+/// Encodes RGB rows as an uncompressed 24-bit BMP
+pub fn bmp(rows: &[Vec<u8>], width: u32) -> Vec<u8> {
+    let height = rows.len() as u32;
+    // Each row is padded to a multiple of 4 bytes
+    let stride = (width * 3).div_ceil(4) * 4;
+    let data_len = stride * height;
+    let mut out = Vec::with_capacity(54 + data_len as usize);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(54 + data_len).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&54u32.to_le_bytes());
+    out.extend_from_slice(&40u32.to_le_bytes());
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&24u16.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(&2835u32.to_le_bytes());
+    out.extend_from_slice(&2835u32.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+    // Rows are stored bottom-up, pixels as BGR
+    for row in rows.iter().rev() {
+        for pixel in row.chunks_exact(3) {
+            out.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+        out.resize(out.len() + (stride - width * 3) as usize, 0);
+    }
+    out
+}
+
+/// Encodes RGB rows as an 8-bit truecolor PNG
+pub fn png(rows: &[Vec<u8>], width: u32) -> Vec<u8> {
+    let height = rows.len() as u32;
+    // Every scanline starts with filter type 0 (none)
+    let mut raw = Vec::with_capacity(rows.len() * (width as usize * 3 + 1));
+    for row in rows {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut out, b"IHDR", &ihdr);
+    png_chunk(&mut out, b"IDAT", &zlib(&raw, width as usize * 3 + 1));
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+const MAX_MATCH: usize = 258;
+const MAX_DISTANCE: usize = 32768;
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    len: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= value << self.len;
+        self.len += len;
+        while self.len >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    // Huffman codes are packed starting from their most significant bit
+    fn write_code(&mut self, code: u32, len: u32) {
+        self.write(code.reverse_bits() >> (32 - len), len);
+    }
+
+    fn write_symbol(&mut self, symbol: u16) {
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol as u32, 8),
+            144..=255 => self.write_code(0x190 + (symbol as u32 - 144), 9),
+            256..=279 => self.write_code(symbol as u32 - 256, 7),
+            _ => self.write_code(0xc0 + (symbol as u32 - 280), 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// Compresses data into a zlib stream made of a single fixed-Huffman deflate block.
+/// Rendered images repeat either the previous pixel or the previous scanline, so
+/// matches are only searched at those two distances.
+fn zlib(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: vec![0x78, 0x01],
+        bits: 0,
+        len: 0,
+    };
+    // Final block, fixed Huffman codes
+    writer.write(0b011, 3);
+    let mut i = 0;
+    while i < data.len() {
+        let mut best = (0, 0);
+        for distance in [3, stride] {
+            if distance > i || distance > MAX_DISTANCE {
+                continue;
+            }
+            let max = MAX_MATCH.min(data.len() - i);
+            let mut len = 0;
+            while len < max && data[i + len] == data[i + len - distance] {
+                len += 1;
+            }
+            if len > best.0 {
+                best = (len, distance);
+            }
+        }
+        let (len, distance) = best;
+        if len < 3 {
+            writer.write_symbol(data[i] as u16);
+            i += 1;
+            continue;
+        }
+        let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap();
+        writer.write_symbol(257 + code as u16);
+        writer.write((len - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+        let code = DIST_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+        writer.write_code(code as u32, 5);
+        writer.write((distance - DIST_BASE[code] as usize) as u32, DIST_EXTRA[code] as u32);
+        i += len;
+    }
+    writer.write_symbol(256);
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
@@ -1,15 +1,109 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-// use cw_storage_plus::Map;
+use crate::msg::{AccessPolicy, ModerationAction, RateLimit, Region};
 
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Binary, Empty};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct State {
-	pub x_size: u8,
-	pub y_size: u8,
-	pub z_values: String,
+	pub x_size: u16,
+	pub y_size: u16,
+	pub admin: Addr,
+	pub rate_limit: Option<RateLimit>,
 }
 
 pub const STATE: Item<State> = Item::new("state");
+
+// z_values split into chunks of grid::CHUNK_SIZE points, keyed by chunk index.
+// Snapshotted every block so the grid can be queried at past heights.
+pub const Z_CHUNKS: SnapshotMap<u32, String> = SnapshotMap::new(
+	"z_chunks",
+	"z_chunks__checkpoints",
+	"z_chunks__changelog",
+	Strategy::EveryBlock,
+);
+
+// Last painter of each point, keyed by grid index
+pub const PAINTERS: SnapshotMap<u32, Addr> = SnapshotMap::new(
+	"painters",
+	"painters__checkpoints",
+	"painters__changelog",
+	Strategy::EveryBlock,
+);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Change {
+	pub height: u64,
+	pub x: u16,
+	pub y: u16,
+	pub z: String,
+	pub painter: Addr,
+}
+
+// Ring buffer of the most recent changes, keyed by sequence number
+pub const CHANGES: Map<u64, Change> = Map::new("changes");
+// Sequence number of the next change
+pub const NEXT_CHANGE: Item<u64> = Item::new("next_change");
+
+pub const MODERATORS: Map<&Addr, Empty> = Map::new("moderators");
+pub const BANNED: Map<&Addr, Empty> = Map::new("banned");
+
+// Rectangles that Set cannot change, keyed by id
+pub const PROTECTED_REGIONS: Map<u64, Region> = Map::new("protected_regions");
+pub const NEXT_PROTECTED_REGION: Item<u64> = Item::new("next_protected_region");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ModerationEntry {
+	pub height: u64,
+	pub moderator: Addr,
+	pub action: ModerationAction,
+}
+
+pub const MODERATION_LOG: Map<u64, ModerationEntry> = Map::new("moderation_log");
+pub const NEXT_MODERATION: Item<u64> = Item::new("next_moderation");
+
+// Last time each address set a point, in the unit of the cooldown (block height or seconds)
+pub const LAST_PAINT: Map<&Addr, u64> = Map::new("last_paint");
+// Epoch index and number of points set in it, per address
+pub const EPOCH_USAGE: Map<&Addr, (u64, u32)> = Map::new("epoch_usage");
+
+// Who can set points, Open if missing
+pub const ACCESS_POLICY: Item<AccessPolicy> = Item::new("access_policy");
+
+// Creator allowed to send InitChunk; present until FinalizeInit opens the canvas
+pub const INIT_CREATOR: Item<Addr> = Item::new("init_creator");
+
+// Colors of a palette mode canvas, absent for an RGB canvas. Snapshotted so past
+// grids resolve their indices with the palette of the time.
+pub const PALETTE: SnapshotItem<Vec<String>> = SnapshotItem::new(
+	"palette",
+	"palette__checkpoints",
+	"palette__changelog",
+	Strategy::EveryBlock,
+);
+// Number of palette changes, which makes older proposals stale
+pub const PALETTE_VERSION: Item<u64> = Item::new("palette_version");
+// Bits per point of a canvas storing color indices (palette or grayscale), absent for 24 bit RGB
+pub const BIT_DEPTH: Item<u8> = Item::new("bit_depth");
+// Color indices of the points of a canvas with a BIT_DEPTH, packed in chunks of grid::CHUNK_SIZE
+pub const PACKED_CHUNKS: SnapshotMap<u32, Binary> = SnapshotMap::new(
+	"packed_chunks",
+	"packed_chunks__checkpoints",
+	"packed_chunks__changelog",
+	Strategy::EveryBlock,
+);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PaletteProposal {
+	pub colors: Vec<String>,
+	pub proposer: Addr,
+	// PALETTE_VERSION when proposed
+	pub version: u64,
+	pub votes: Vec<Addr>,
+}
+
+// Open palette proposals, removed once applied
+pub const PALETTE_PROPOSALS: Map<u64, PaletteProposal> = Map::new("palette_proposals");
+pub const NEXT_PALETTE_PROPOSAL: Item<u64> = Item::new("next_palette_proposal");
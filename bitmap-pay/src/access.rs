@@ -0,0 +1,197 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Deps, DepsMut, MessageInfo, Response, StdResult};
+use sha2::{Digest, Sha256};
+
+use crate::canvas;
+use crate::error::ContractError;
+use crate::msg::{AccessPolicy, GetTrustedRouterResponse};
+use crate::state::{ACCESS_POLICY, TRUSTED_ROUTER};
+
+/// The subset of the cw721 query API needed to check ownership
+#[cw_serde]
+enum Cw721QueryMsg {
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+struct TokensResponse {
+    tokens: Vec<String>,
+}
+
+fn decode_hash(hash: &str) -> Option<[u8; 32]> {
+    hex::decode(hash).ok()?.try_into().ok()
+}
+
+/// Checks a proof that sha256(sender) is a leaf of the tree. Pairs of nodes are
+/// sorted before hashing, so proofs only list the sibling hashes.
+fn verify_merkle_proof(root: &str, sender: &Addr, proof: &[String]) -> bool {
+    let Some(root) = decode_hash(root) else {
+        return false;
+    };
+    let mut hash: [u8; 32] = Sha256::digest(sender.as_bytes()).into();
+    for sibling in proof {
+        let Some(sibling) = decode_hash(sibling) else {
+            return false;
+        };
+        let (a, b) = if hash <= sibling { (hash, sibling) } else { (sibling, hash) };
+        hash = Sha256::new().chain_update(a).chain_update(b).finalize().into();
+    }
+    hash == root
+}
+
+/// Rejects senders that the access policy does not allow to paint
+pub fn check_access(
+    deps: Deps,
+    canvas_id: u32,
+    sender: &Addr,
+    proof: Option<Vec<String>>,
+) -> Result<(), ContractError> {
+    let policy = ACCESS_POLICY.may_load(deps.storage, canvas_id)?;
+    let eligible = match policy.unwrap_or(AccessPolicy::Open {}) {
+        AccessPolicy::Open {} => true,
+        AccessPolicy::Allowlist(addresses) => addresses.contains(sender),
+        AccessPolicy::HoldsNativeBalance { denom, min } => {
+            deps.querier.query_balance(sender, denom)?.amount >= min
+        }
+        AccessPolicy::HoldsCw721 { collection } => {
+            let res: TokensResponse = deps.querier.query_wasm_smart(
+                collection,
+                &Cw721QueryMsg::Tokens {
+                    owner: sender.to_string(),
+                    start_after: None,
+                    limit: Some(1),
+                },
+            )?;
+            !res.tokens.is_empty()
+        }
+        AccessPolicy::MerkleAllowlist { root } => {
+            verify_merkle_proof(&root, sender, &proof.unwrap_or_default())
+        }
+    };
+    if !eligible {
+        return Err(ContractError::NotEligible {});
+    }
+    Ok(())
+}
+
+fn validate_policy(deps: Deps, policy: &AccessPolicy) -> Result<(), ContractError> {
+    match policy {
+        AccessPolicy::Allowlist(addresses) => {
+            for address in addresses {
+                deps.api.addr_validate(address.as_str())?;
+            }
+        }
+        AccessPolicy::HoldsCw721 { collection } => {
+            deps.api.addr_validate(collection)?;
+        }
+        AccessPolicy::MerkleAllowlist { root } => {
+            if decode_hash(root).is_none() {
+                return Err(ContractError::InvalidMerkleRoot {});
+            }
+        }
+        AccessPolicy::Open {} | AccessPolicy::HoldsNativeBalance { .. } => {}
+    }
+    Ok(())
+}
+
+pub fn save_policy(
+    deps: DepsMut,
+    canvas_id: u32,
+    policy: &AccessPolicy,
+) -> Result<(), ContractError> {
+    validate_policy(deps.as_ref(), policy)?;
+    ACCESS_POLICY.save(deps.storage, canvas_id, policy)?;
+    Ok(())
+}
+
+pub fn set_access_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    canvas_id: u32,
+    policy: AccessPolicy,
+) -> Result<Response, ContractError> {
+    if canvas::load(deps.storage, canvas_id)?.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    save_policy(deps, canvas_id, &policy)?;
+    Ok(Response::new().add_attribute("action", "set_access_policy"))
+}
+
+/// Returns the painter of a Set: `on_behalf_of` when the sender is the trusted
+/// router of the canvas, otherwise the sender
+pub fn painter(
+    deps: Deps,
+    canvas_id: u32,
+    sender: &Addr,
+    on_behalf_of: Option<String>,
+) -> Result<Addr, ContractError> {
+    let Some(painter) = on_behalf_of else {
+        return Ok(sender.clone());
+    };
+    if TRUSTED_ROUTER.may_load(deps.storage, canvas_id)?.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(deps.api.addr_validate(&painter)?)
+}
+
+pub fn set_trusted_router(
+    deps: DepsMut,
+    info: MessageInfo,
+    canvas_id: u32,
+    router: Option<String>,
+) -> Result<Response, ContractError> {
+    if canvas::load(deps.storage, canvas_id)?.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    match router {
+        Some(router) => {
+            let router = deps.api.addr_validate(&router)?;
+            TRUSTED_ROUTER.save(deps.storage, canvas_id, &router)?;
+        }
+        None => TRUSTED_ROUTER.remove(deps.storage, canvas_id),
+    }
+    Ok(Response::new().add_attribute("action", "set_trusted_router"))
+}
+
+pub fn update_allowlist(
+    deps: DepsMut,
+    info: MessageInfo,
+    canvas_id: u32,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    if canvas::load(deps.storage, canvas_id)?.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let Some(AccessPolicy::Allowlist(mut addresses)) = ACCESS_POLICY.may_load(deps.storage, canvas_id)? else {
+        return Err(ContractError::NotAllowlist {});
+    };
+    for address in add {
+        let address = deps.api.addr_validate(&address)?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    for address in remove {
+        let address = deps.api.addr_validate(&address)?;
+        addresses.retain(|a| *a != address);
+    }
+    ACCESS_POLICY.save(deps.storage, canvas_id, &AccessPolicy::Allowlist(addresses))?;
+    Ok(Response::new().add_attribute("action", "update_allowlist"))
+}
+
+pub fn get_trusted_router(deps: Deps, canvas_id: u32) -> StdResult<GetTrustedRouterResponse> {
+    canvas::load(deps.storage, canvas_id)?;
+    Ok(GetTrustedRouterResponse {
+        router: TRUSTED_ROUTER.may_load(deps.storage, canvas_id)?,
+    })
+}
+
+pub fn get_access_policy(deps: Deps, canvas_id: u32) -> StdResult<AccessPolicy> {
+    canvas::load(deps.storage, canvas_id)?;
+    Ok(ACCESS_POLICY.may_load(deps.storage, canvas_id)?.unwrap_or(AccessPolicy::Open {}))
+}
@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Coin, Deps, Empty, Order, StdResult, Storage};
+use cw_storage_plus::Bound;
+
+use crate::canvas;
+use crate::grid;
+use crate::msg::{
+    ColorCount, GetColorHistogramResponse, GetStatsResponse, GetTopPaintersResponse, MostPainted,
+    PainterInfo, PainterRanking,
+};
+use crate::state::{
+    PainterStats, CANVAS_PAINTERS, CANVAS_STATS, COLOR_COUNTS, HELD_RANK, MOST_PAINTED,
+    PAINTER_STATS, SPENT_RANK,
+};
+
+pub const DEFAULT_TOP_PAINTERS_LIMIT: u32 = 10;
+pub const MAX_TOP_PAINTERS_LIMIT: u32 = 100;
+pub const DEFAULT_COLORS_LIMIT: u32 = 100;
+pub const MAX_COLORS_LIMIT: u32 = 1_000;
+
+/// Seeds the color histogram of a new canvas. Points without z_values are "000000".
+pub fn init_colors(
+    storage: &mut dyn Storage,
+    canvas_id: u32,
+    grid_len: u32,
+    z_values: Option<&str>,
+) -> StdResult<()> {
+    match z_values {
+        Some(z_values) => update_colors(storage, canvas_id, None, z_values),
+        None if grid_len > 0 => COLOR_COUNTS.save(storage, (canvas_id, "000000"), &grid_len),
+        None => Ok(()),
+    }
+}
+
+/// Moves the points of old_z_values, if any, to the colors of z_values in the histogram
+pub fn update_colors(
+    storage: &mut dyn Storage,
+    canvas_id: u32,
+    old_z_values: Option<&str>,
+    z_values: &str,
+) -> StdResult<()> {
+    let mut deltas: BTreeMap<String, i64> = BTreeMap::new();
+    let colors = |z_values: &str| {
+        z_values
+            .as_bytes()
+            .chunks(grid::Z_LEN)
+            .map(|z| String::from_utf8_lossy(z).to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+    for z in colors(old_z_values.unwrap_or_default()) {
+        *deltas.entry(z).or_default() -= 1;
+    }
+    for z in colors(z_values) {
+        *deltas.entry(z).or_default() += 1;
+    }
+    for (z, delta) in deltas.into_iter().filter(|(_, delta)| *delta != 0) {
+        let key = (canvas_id, z.as_str());
+        let count = COLOR_COUNTS.may_load(storage, key)?.unwrap_or_default() as i64 + delta;
+        if count > 0 {
+            COLOR_COUNTS.save(storage, key, &(count as u32))?;
+        } else {
+            COLOR_COUNTS.remove(storage, key);
+        }
+    }
+    Ok(())
+}
+
+fn save_painter(
+    storage: &mut dyn Storage,
+    canvas_id: u32,
+    painter: &Addr,
+    old: &PainterStats,
+    new: &PainterStats,
+) -> StdResult<()> {
+    HELD_RANK.remove(storage, (canvas_id, old.pixels_held, painter));
+    SPENT_RANK.remove(storage, (canvas_id, old.amount_spent, painter));
+    HELD_RANK.save(storage, (canvas_id, new.pixels_held, painter), &Empty {})?;
+    SPENT_RANK.save(storage, (canvas_id, new.amount_spent, painter), &Empty {})?;
+    PAINTER_STATS.save(storage, (canvas_id, painter), new)
+}
+
+/// Updates the canvas stats, the painter ranks, the color histogram and the most
+/// painted point after a paid Set of point idx. update_count is the count after the Set.
+#[allow(clippy::too_many_arguments)]
+pub fn record_set(
+    storage: &mut dyn Storage,
+    canvas_id: u32,
+    idx: u32,
+    painter: &Addr,
+    previous_painter: Option<&Addr>,
+    old_z: &str,
+    z: &str,
+    update_count: u32,
+    cost: u128,
+) -> StdResult<()> {
+    let old = PAINTER_STATS
+        .may_load(storage, (canvas_id, painter))?
+        .unwrap_or_default();
+    let mut new = PainterStats {
+        pixels_held: old.pixels_held,
+        amount_spent: old.amount_spent.saturating_add(cost),
+        paints: old.paints + 1,
+    };
+    if previous_painter != Some(painter) {
+        new.pixels_held += 1;
+        if let Some(previous_painter) = previous_painter {
+            let previous_old = PAINTER_STATS.load(storage, (canvas_id, previous_painter))?;
+            let previous_new = PainterStats {
+                pixels_held: previous_old.pixels_held - 1,
+                ..previous_old.clone()
+            };
+            save_painter(storage, canvas_id, previous_painter, &previous_old, &previous_new)?;
+        }
+    }
+    save_painter(storage, canvas_id, painter, &old, &new)?;
+
+    let mut stats = CANVAS_STATS.may_load(storage, canvas_id)?.unwrap_or_default();
+    stats.paints += 1;
+    stats.revenue = stats.revenue.saturating_add(cost);
+    if !CANVAS_PAINTERS.has(storage, (canvas_id, painter)) {
+        CANVAS_PAINTERS.save(storage, (canvas_id, painter), &Empty {})?;
+        stats.unique_painters += 1;
+    }
+    CANVAS_STATS.save(storage, canvas_id, &stats)?;
+
+    update_colors(storage, canvas_id, Some(old_z), z)?;
+
+    let most = MOST_PAINTED.may_load(storage, canvas_id)?.map(|(_, most)| most);
+    if update_count > most.unwrap_or_default() {
+        MOST_PAINTED.save(storage, canvas_id, &(idx, update_count))?;
+    }
+    Ok(())
+}
+
+pub fn get_stats(deps: Deps, canvas_id: u32) -> StdResult<GetStatsResponse> {
+    let state = canvas::load(deps.storage, canvas_id)?;
+    let stats = CANVAS_STATS.may_load(deps.storage, canvas_id)?.unwrap_or_default();
+    let most_painted = MOST_PAINTED
+        .may_load(deps.storage, canvas_id)?
+        .map(|(idx, update_count)| MostPainted {
+            x: (idx % state.x_size as u32) as u16,
+            y: (idx / state.x_size as u32) as u16,
+            update_count,
+        });
+    Ok(GetStatsResponse {
+        paints: stats.paints,
+        pixels_set: state.num_set,
+        unique_painters: stats.unique_painters,
+        total_revenue: Coin::new(stats.revenue, state.fee_denom),
+        most_painted,
+    })
+}
+
+/// Returns the top painters of a canvas, highest first. Ties are broken by address, descending.
+pub fn top_painters(
+    deps: Deps,
+    canvas_id: u32,
+    by: PainterRanking,
+    limit: Option<u32>,
+) -> StdResult<GetTopPaintersResponse> {
+    let state = canvas::load(deps.storage, canvas_id)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_TOP_PAINTERS_LIMIT)
+        .min(MAX_TOP_PAINTERS_LIMIT) as usize;
+    let painters: Vec<Addr> = match by {
+        PainterRanking::PixelsHeld => HELD_RANK
+            .sub_prefix(canvas_id)
+            .keys(deps.storage, None, None, Order::Descending)
+            .take(limit)
+            .map(|key| key.map(|(_, painter)| painter))
+            .collect::<StdResult<_>>()?,
+        PainterRanking::AmountSpent => SPENT_RANK
+            .sub_prefix(canvas_id)
+            .keys(deps.storage, None, None, Order::Descending)
+            .take(limit)
+            .map(|key| key.map(|(_, painter)| painter))
+            .collect::<StdResult<_>>()?,
+    };
+    let painters = painters
+        .into_iter()
+        .map(|painter| {
+            let stats = PAINTER_STATS.load(deps.storage, (canvas_id, &painter))?;
+            Ok(PainterInfo {
+                painter,
+                pixels_held: stats.pixels_held,
+                amount_spent: Coin::new(stats.amount_spent, state.fee_denom.clone()),
+                paints: stats.paints,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetTopPaintersResponse { painters })
+}
+
+pub fn color_histogram(
+    deps: Deps,
+    canvas_id: u32,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetColorHistogramResponse> {
+    let limit = limit.unwrap_or(DEFAULT_COLORS_LIMIT).min(MAX_COLORS_LIMIT) as usize;
+    let start_after = start_after.map(|z| z.to_ascii_lowercase());
+    canvas::load(deps.storage, canvas_id)?;
+    let colors = COLOR_COUNTS
+        .prefix(canvas_id)
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(color, count)| ColorCount { color, count }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetColorHistogramResponse { colors })
+}
@@ -0,0 +1,138 @@
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+};
+use cw_storage_plus::Bound;
+
+use crate::access;
+use crate::analytics;
+use crate::error::ContractError;
+use crate::grid;
+use crate::msg::{CanvasConfig, CanvasInfo, ListCanvasesResponse};
+use crate::state::{State, ADMIN, CANVASES, DENOM_TOTALS, INIT_CREATOR, LAYER_PARENT, NEXT_CANVAS_ID};
+
+/// Canvas created at instantiate, used by messages that omit canvas_id
+pub const DEFAULT_CANVAS_ID: u32 = 0;
+pub const DEFAULT_CANVASES_LIMIT: u32 = 30;
+pub const MAX_CANVASES_LIMIT: u32 = 100;
+
+pub fn id(canvas_id: Option<u32>) -> u32 {
+    canvas_id.unwrap_or(DEFAULT_CANVAS_ID)
+}
+
+pub fn load(storage: &dyn Storage, canvas_id: u32) -> StdResult<State> {
+    CANVASES
+        .may_load(storage, canvas_id)?
+        .ok_or_else(|| StdError::generic_err("Canvas not found"))
+}
+
+/// Stores a new canvas under the next canvas_id. The admin defaults to creator,
+/// who also sends InitChunk under chunked_init.
+pub fn create(
+    mut deps: DepsMut,
+    env: &Env,
+    creator: &Addr,
+    config: CanvasConfig,
+) -> Result<u32, ContractError> {
+    let canvas_id = NEXT_CANVAS_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_CANVAS_ID.save(deps.storage, &(canvas_id + 1))?;
+    let grid_len = grid::grid_len(config.x_size, config.y_size);
+    if config.chunked_init.unwrap_or(false) {
+        INIT_CREATOR.save(deps.storage, canvas_id, creator)?;
+    }
+    // Points default to "000000", so only explicit z_values are written to storage
+    if let Some(ref z) = config.z_values {
+        if z.len() != grid_len as usize * grid::Z_LEN || !grid::is_hex(z) {
+            return Err(ContractError::InvalidZValue {});
+        }
+        grid::save_z_values(deps.storage, canvas_id, z, env.block.height)?;
+    }
+    analytics::init_colors(
+        deps.storage,
+        canvas_id,
+        grid_len,
+        config.z_values.as_deref(),
+    )?;
+    if let Some(ref policy) = config.access_policy {
+        access::save_policy(deps.branch(), canvas_id, policy)?;
+    }
+    let admin = match config.admin {
+        Some(ref admin) => deps.api.addr_validate(admin)?,
+        None => creator.clone(),
+    };
+    // List the fee denom in the treasury before any revenue comes in
+    if !DENOM_TOTALS.has(deps.storage, &config.fee_denom) {
+        DENOM_TOTALS.save(deps.storage, &config.fee_denom, &Default::default())?;
+    }
+    let state = State {
+        x_size: config.x_size,
+        y_size: config.y_size,
+        admin,
+        recipient: config.recipient,
+        supply_base_fee: config.supply_base_fee,
+        supply_fee_factor: config.supply_fee_factor,
+        update_base_fee: config.update_base_fee,
+        update_fee_factor: config.update_fee_factor,
+        fee_factor_scale: config.fee_factor_scale,
+        fee_denom: config.fee_denom,
+        max_update_multiplier: config.max_update_multiplier,
+        num_set: 0,
+    };
+    CANVASES.save(deps.storage, canvas_id, &state)?;
+    Ok(canvas_id)
+}
+
+pub fn create_canvas(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Box<CanvasConfig>,
+) -> Result<Response, ContractError> {
+    if ADMIN.load(deps.storage)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let (x_size, y_size) = (config.x_size, config.y_size);
+    let canvas_id = create(deps, &env, &info.sender, *config)?;
+    Ok(Response::new()
+        .add_attribute("action", "create_canvas")
+        .add_attribute("canvas_id", canvas_id.to_string())
+        .add_attribute("x_size", x_size.to_string())
+        .add_attribute("y_size", y_size.to_string()))
+}
+
+pub fn list_canvases(
+    deps: Deps,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<ListCanvasesResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_CANVASES_LIMIT)
+        .min(MAX_CANVASES_LIMIT) as usize;
+    let canvases = CANVASES
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        // Layers are listed by GetLayers of their canvas
+        .filter(|item| {
+            item.as_ref()
+                .map_or(true, |(canvas_id, _)| !LAYER_PARENT.has(deps.storage, *canvas_id))
+        })
+        .take(limit)
+        .map(|item| {
+            let (canvas_id, state) = item?;
+            Ok(CanvasInfo {
+                canvas_id,
+                x_size: state.x_size,
+                y_size: state.y_size,
+                admin: state.admin,
+                recipient: state.recipient,
+                fee_denom: state.fee_denom,
+                num_set: state.num_set,
+                initializing: INIT_CREATOR.has(deps.storage, canvas_id),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ListCanvasesResponse { canvases })
+}
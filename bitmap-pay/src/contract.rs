@@ -3,9 +3,19 @@ use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 use cw2::set_contract_version;
 
+use crate::access;
+use crate::analytics;
+use crate::canvas;
+use crate::changes;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::STATE;
+use crate::grid;
+use crate::heatmap;
+use crate::init;
+use crate::layers;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, Region};
+use crate::render;
+use crate::state::{ADMIN, CANVASES, LAST_PAINTED, PAINTERS, UPDATE_COUNTS};
+use crate::treasury;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:staking-contract";
@@ -14,59 +24,82 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let string_size = (msg.x_size as u32) * (msg.y_size as u32) * 6;
-    let z_values = match msg.z_values {
-        Some(ref z) => {
-            if z.len() != string_size as usize {
-                return Err(ContractError::InvalidZValue {});
-            }
-            z.clone()
-        }
-        None => "0".repeat(string_size as usize),
-    };
-    let grid_len = (msg.x_size as usize) * (msg.y_size as usize);
-    let bitfield_len = (grid_len + 7) / 8;
-    let state = crate::state::State {
-        x_size: msg.x_size,
-        y_size: msg.y_size,
-        z_values,
-        recipient: msg.recipient,
-        supply_base_fee: msg.supply_base_fee,
-        supply_fee_factor: msg.supply_fee_factor,
-        update_base_fee: msg.update_base_fee,
-        update_fee_factor: msg.update_fee_factor,
-        fee_factor_scale: msg.fee_factor_scale,
-        fee_denom: msg.fee_denom.clone(),
-        set_points: vec![0u8; bitfield_len],
-        update_counts: vec![0u8; grid_len],
+    let admin = match msg.admin {
+        Some(ref admin) => deps.api.addr_validate(admin)?,
+        None => info.sender.clone(),
     };
-    STATE.save(deps.storage, &state)?;
-    Ok(Response::new()
+    ADMIN.save(deps.storage, &admin)?;
+    let res = Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("x_size", msg.x_size.to_string())
         .add_attribute("y_size", msg.y_size.to_string())
+        .add_attribute("admin", admin)
         .add_attribute("supply_base_fee", msg.supply_base_fee.to_string())
         .add_attribute("supply_fee_factor", msg.supply_fee_factor.to_string())
         .add_attribute("update_base_fee", msg.update_base_fee.to_string())
         .add_attribute("update_fee_factor", msg.update_fee_factor.to_string())
         .add_attribute("fee_factor_scale", msg.fee_factor_scale.to_string())
-        .add_attribute("fee_denom", msg.fee_denom.clone()))
+        .add_attribute("fee_denom", msg.fee_denom.clone());
+    // The instantiated canvas is canvas::DEFAULT_CANVAS_ID
+    canvas::create(deps, &env, &info.sender, msg.into())?;
+    Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Set { x, y, z } => execute::set(deps, x, y, z, info),
+        ExecuteMsg::Set {
+            canvas_id,
+            layer,
+            x,
+            y,
+            z,
+            blend,
+            proof,
+            on_behalf_of,
+        } => {
+            let canvas_id = layers::resolve(deps.storage, canvas::id(canvas_id), layer.as_deref())?;
+            execute::set(deps, env, canvas_id, x, y, z, blend, proof, on_behalf_of, info)
+        }
+        ExecuteMsg::SetAccessPolicy { canvas_id, policy } => {
+            access::set_access_policy(deps, info, canvas::id(canvas_id), policy)
+        }
+        ExecuteMsg::SetTrustedRouter { canvas_id, router } => {
+            access::set_trusted_router(deps, info, canvas::id(canvas_id), router)
+        }
+        ExecuteMsg::UpdateAllowlist {
+            canvas_id,
+            add,
+            remove,
+        } => access::update_allowlist(deps, info, canvas::id(canvas_id), add, remove),
+        ExecuteMsg::InitChunk {
+            canvas_id,
+            offset,
+            data,
+        } => init::init_chunk(deps, env, info, canvas::id(canvas_id), offset, data),
+        ExecuteMsg::FinalizeInit { canvas_id } => {
+            init::finalize_init(deps, info, canvas::id(canvas_id))
+        }
+        ExecuteMsg::CreateCanvas { config } => canvas::create_canvas(deps, env, info, config),
+        ExecuteMsg::AddLayer {
+            canvas_id,
+            name,
+            transparent,
+            config,
+        } => layers::add_layer(deps, env, info, canvas::id(canvas_id), name, transparent, *config),
+        ExecuteMsg::Withdraw { denom, amount, to } => {
+            treasury::withdraw(deps, env, info, denom, amount, to)
+        }
     }
 }
 
@@ -75,6 +108,10 @@ pub mod execute {
 
     use cosmwasm_std::{BankMsg, MessageInfo};
 
+    use crate::blend;
+    use crate::msg::BlendMode;
+    use crate::state::State;
+
     // Exponential bonding curve: cost = base * e^(factor * num_points_set)
     pub fn bonding_curve(base: u128, factor: f64, num_set: usize) -> u128 {
         let base_f = base as f64;
@@ -84,40 +121,59 @@ pub mod execute {
         result.round() as u128
     }
 
-    pub fn set(
-        deps: DepsMut,
-        x: u8,
-        y: u8,
-        z: String,
-        info: MessageInfo,
-    ) -> Result<Response, ContractError> {
-        let mut state = STATE.load(deps.storage)?;
-        if x > state.x_size || y > state.y_size {
-            return Err(ContractError::IndexOutOfBounds {});
-        }
-        let new_value_length = z.len();
-        if new_value_length != 6 {
-            return Err(ContractError::InvalidZValue {});
-        }
-
-        let idx = (y as usize) * (state.x_size as usize) + (x as usize);
-    let byte_idx = idx / 8;
-    let bit_idx = idx % 8;
-    let already_set = (state.set_points[byte_idx] & (1 << bit_idx)) != 0;
-        let update_count = state.update_counts[idx];
-        let effective_update_count = update_count;
-        let num_set_points = state.set_points.iter().filter(|b| **b != 0).count();
-        let supply_curve_cost = bonding_curve(
+    // Supply curve, based on the number of points set so far
+    pub fn supply_curve_cost(state: &State) -> u128 {
+        bonding_curve(
             state.supply_base_fee,
             state.supply_fee_factor as f64 / state.fee_factor_scale as f64,
-            num_set_points,
-        );
-        let update_curve_cost = bonding_curve(
+            state.num_set as usize,
+        )
+    }
+
+    // Update curve for a point, capped at update_base_fee * max_update_multiplier if set
+    pub fn update_curve_cost(state: &State, update_count: u32) -> u128 {
+        let cost = bonding_curve(
             state.update_base_fee,
             state.update_fee_factor as f64 / state.fee_factor_scale as f64,
-            effective_update_count as usize,
+            update_count as usize,
         );
-        let set_point_cost = supply_curve_cost + update_curve_cost;
+        match state.max_update_multiplier {
+            Some(max) => cost.min(state.update_base_fee.saturating_mul(max)),
+            None => cost,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn set(
+        deps: DepsMut,
+        env: Env,
+        canvas_id: u32,
+        x: u16,
+        y: u16,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+        on_behalf_of: Option<String>,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        let mut state = canvas::load(deps.storage, canvas_id)?;
+        init::ensure_open(deps.storage, canvas_id)?;
+        // A trusted router pays, but the point is painted by the address it acts for
+        let painter = access::painter(deps.as_ref(), canvas_id, &info.sender, on_behalf_of)?;
+        access::check_access(deps.as_ref(), canvas_id, &painter, proof)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y)
+            .ok_or(ContractError::IndexOutOfBounds {})?;
+        if blend::parse_paint(&z).is_none() {
+            return Err(ContractError::InvalidPaint {});
+        }
+
+        let already_set = grid::is_set(deps.storage, canvas_id, idx)?;
+        let update_count = UPDATE_COUNTS
+            .may_load(deps.storage, (canvas_id, idx))?
+            .unwrap_or_default();
+        let supply_curve_cost = supply_curve_cost(&state);
+        let update_curve_cost = update_curve_cost(&state, update_count);
+        let set_point_cost = supply_curve_cost.saturating_add(update_curve_cost);
         let sent = info
             .funds
             .iter()
@@ -127,6 +183,13 @@ pub mod execute {
         if sent < set_point_cost {
             return Err(ContractError::InsufficientFunds {});
         }
+        treasury::record_paint(
+            deps.storage,
+            &painter,
+            &info.funds,
+            &state.fee_denom,
+            set_point_cost,
+        )?;
         // Transfer only the required cost to recipient
         let bank_msg = BankMsg::Send {
             to_address: state.recipient.clone(),
@@ -136,27 +199,45 @@ pub mod execute {
             }],
         };
         // Update grid, set_points, and update_counts
-        let y_offset = (y as u32) * (state.x_size as u32) * 6;
-        let x_offset = (x as u32) * 6;
-        let start = (y_offset + x_offset) as usize;
-        let end = (start + 6) as usize;
-        state.z_values.replace_range(start..end, &z);
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let old_z = grid::load_point(deps.storage, canvas_id, grid_len, idx)?;
+        let z = blend::paint(&old_z, z, &blend.unwrap_or_default())?;
+        let previous_painter = PAINTERS.may_load(deps.storage, (canvas_id, idx))?;
+        grid::save_point(deps.storage, canvas_id, grid_len, idx, &z, env.block.height)?;
+        PAINTERS.save(deps.storage, (canvas_id, idx), &painter, env.block.height)?;
+        analytics::record_set(
+            deps.storage,
+            canvas_id,
+            idx,
+            &painter,
+            previous_painter.as_ref(),
+            &old_z,
+            &z,
+            update_count.saturating_add(1),
+            set_point_cost,
+        )?;
 
         if !already_set {
-            state.set_points[byte_idx] |= 1 << bit_idx;
-        }
-        // increment update count, but cap at 255
-        if state.update_counts[idx] < 255 {
-            state.update_counts[idx] = update_count.saturating_add(1);
+            grid::mark_set(deps.storage, canvas_id, idx)?;
+            state.num_set += 1;
+            CANVASES.save(deps.storage, canvas_id, &state)?;
         }
-        STATE.save(deps.storage, &state)?;
-        Ok(Response::new()
-            .add_message(bank_msg)
+        UPDATE_COUNTS.save(deps.storage, (canvas_id, idx), &update_count.saturating_add(1))?;
+        LAST_PAINTED.save(deps.storage, (canvas_id, idx), &env.block.height)?;
+        changes::record_change(deps.storage, canvas_id, &env, x, y, &z, &painter)?;
+        // Free canvases, such as an owner's layer, have nothing to transfer
+        let res = if set_point_cost > 0 {
+            Response::new().add_message(bank_msg)
+        } else {
+            Response::new()
+        };
+        Ok(res
             .add_attribute("action", "set_point")
+            .add_attribute("canvas_id", canvas_id.to_string())
             .add_attribute("x", x.to_string())
             .add_attribute("y", y.to_string())
             .add_attribute("z", z.to_string())
-            .add_attribute("from", info.sender)
+            .add_attribute("from", painter)
             .add_attribute("cost", set_point_cost.to_string())
             .add_attribute("recipient", state.recipient)
             .add_attribute("already_set", already_set.to_string())
@@ -165,32 +246,171 @@ pub mod execute {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetPoint { x, y } => to_json_binary(&query::get_point(deps, x, y)?),
-        QueryMsg::GetGrid {} => to_json_binary(&query::get_grid(deps)?),
-        QueryMsg::GetCost { x, y } => to_json_binary(&query::get_cost(deps, x, y)?),
-        QueryMsg::GetParams {} => to_json_binary(&query::get_params(deps)?),
+        QueryMsg::GetPoint { canvas_id, x, y } => {
+            to_json_binary(&query::get_point(deps, canvas::id(canvas_id), x, y)?)
+        }
+        QueryMsg::GetGrid {
+            canvas_id,
+            start_after,
+            limit,
+        } => to_json_binary(&query::get_grid(deps, canvas::id(canvas_id), start_after, limit)?),
+        QueryMsg::GetGridAt {
+            canvas_id,
+            height,
+            start_after,
+            limit,
+        } => to_json_binary(&query::get_grid_at(
+            deps,
+            canvas::id(canvas_id),
+            height,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetPointAt {
+            canvas_id,
+            x,
+            y,
+            height,
+        } => to_json_binary(&query::get_point_at(deps, canvas::id(canvas_id), x, y, height)?),
+        QueryMsg::GetRegion {
+            canvas_id,
+            x0,
+            y0,
+            width,
+            height,
+        } => to_json_binary(&query::get_region(
+            deps,
+            canvas::id(canvas_id),
+            x0,
+            y0,
+            width,
+            height,
+        )?),
+        QueryMsg::GetRow { canvas_id, y } => {
+            to_json_binary(&query::get_row(deps, canvas::id(canvas_id), y)?)
+        }
+        QueryMsg::GetPoints { canvas_id, coords } => {
+            to_json_binary(&query::get_points(deps, canvas::id(canvas_id), coords)?)
+        }
+        QueryMsg::RenderSvg {
+            canvas_id,
+            scale,
+            region,
+        } => to_json_binary(&query::render_svg(deps, canvas::id(canvas_id), scale, region)?),
+        QueryMsg::RenderImage {
+            canvas_id,
+            format,
+            scale,
+            region,
+        } => to_json_binary(&query::render_image(
+            deps,
+            canvas::id(canvas_id),
+            format,
+            scale,
+            region,
+        )?),
+        QueryMsg::GetHeatmap {
+            canvas_id,
+            region,
+            recency_bucket,
+        } => to_json_binary(&heatmap::get_heatmap(
+            deps,
+            env,
+            canvas::id(canvas_id),
+            region,
+            recency_bucket,
+        )?),
+        QueryMsg::GetAccessPolicy { canvas_id } => {
+            to_json_binary(&access::get_access_policy(deps, canvas::id(canvas_id))?)
+        }
+        QueryMsg::GetTrustedRouter { canvas_id } => {
+            to_json_binary(&access::get_trusted_router(deps, canvas::id(canvas_id))?)
+        }
+        QueryMsg::GetChangesSince {
+            canvas_id,
+            height,
+            start_after,
+            limit,
+        } => to_json_binary(&changes::changes_since(
+            deps.storage,
+            canvas::id(canvas_id),
+            height,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetCost { canvas_id, x, y } => {
+            to_json_binary(&query::get_cost(deps, canvas::id(canvas_id), x, y)?)
+        }
+        QueryMsg::GetPriceLadder {
+            canvas_id,
+            x,
+            y,
+            steps,
+        } => to_json_binary(&query::get_price_ladder(deps, canvas::id(canvas_id), x, y, steps)?),
+        QueryMsg::GetTreasury {} => to_json_binary(&treasury::get_treasury(deps, env)?),
+        QueryMsg::GetStats { canvas_id } => {
+            to_json_binary(&analytics::get_stats(deps, canvas::id(canvas_id))?)
+        }
+        QueryMsg::GetTopPainters {
+            canvas_id,
+            by,
+            limit,
+        } => {
+            to_json_binary(&analytics::top_painters(deps, canvas::id(canvas_id), by, limit)?)
+        }
+        QueryMsg::GetColorHistogram {
+            canvas_id,
+            start_after,
+            limit,
+        } => to_json_binary(&analytics::color_histogram(
+            deps,
+            canvas::id(canvas_id),
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetLayers { canvas_id } => {
+            to_json_binary(&layers::get_layers(deps, canvas::id(canvas_id))?)
+        }
+        QueryMsg::GetComposite { canvas_id, region } => {
+            to_json_binary(&layers::get_composite(deps, canvas::id(canvas_id), region)?)
+        }
+        QueryMsg::ListCanvases { start_after, limit } => {
+            to_json_binary(&canvas::list_canvases(deps, start_after, limit)?)
+        }
+        QueryMsg::GetParams { canvas_id } => {
+            to_json_binary(&query::get_params(deps, canvas::id(canvas_id))?)
+        }
     }
 }
 
 pub mod query {
     use super::*;
-    use crate::msg::{GetCostResponse, GetGridResponse, GetParamsResponse, GetPointResponse};
-    use crate::state::STATE;
-
-    pub fn get_point(deps: Deps, x: u8, y: u8) -> StdResult<GetPointResponse> {
-        let state = STATE.load(deps.storage)?;
-        let idx = (y as usize) * (state.x_size as usize) + (x as usize);
-        let byte_idx = idx / 8;
-        let bit_idx = idx % 8;
-        let y_offset = (y as u32) * (state.x_size as u32) * 6;
-        let x_offset = (x as u32) * 6;
-        let start = (y_offset + x_offset) as usize;
-        let end = (start + 6) as usize;
-        let point = state.z_values[start..end].to_string();
-        let is_set = (state.set_points[byte_idx] & (1 << bit_idx)) != 0;
-        let update_count = state.update_counts[idx];
+    use std::collections::btree_map::Entry;
+    use std::collections::BTreeMap;
+
+    use cosmwasm_std::{Binary, StdError};
+
+    use crate::msg::{
+        GetCostResponse, GetGridResponse, GetParamsResponse, GetPointAtResponse, GetPointResponse,
+        GetPointsResponse, GetPriceLadderResponse, GetRegionResponse, GetRowResponse, ImageFormat,
+        RenderImageResponse, RenderSvgResponse,
+    };
+    use crate::state::{PAINTERS, UPDATE_COUNTS};
+
+    /// Maximum number of prices returned by GetPriceLadder for each curve
+    pub const MAX_LADDER_STEPS: u32 = 100;
+
+    pub fn get_point(deps: Deps, canvas_id: u32, x: u16, y: u16) -> StdResult<GetPointResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let point = grid::load_point(deps.storage, canvas_id, grid_len, idx)?;
+        let is_set = grid::is_set(deps.storage, canvas_id, idx)?;
+        let update_count = UPDATE_COUNTS
+            .may_load(deps.storage, (canvas_id, idx))?
+            .unwrap_or_default();
         Ok(GetPointResponse {
             point,
             is_set,
@@ -198,38 +418,243 @@ pub mod query {
         })
     }
 
-    pub fn get_grid(deps: Deps) -> StdResult<GetGridResponse> {
-        let state = STATE.load(deps.storage)?;
+    pub fn get_grid(
+        deps: Deps,
+        canvas_id: u32,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<GetGridResponse> {
+        grid_page(deps, canvas_id, None, start_after, limit)
+    }
+
+    pub fn get_grid_at(
+        deps: Deps,
+        canvas_id: u32,
+        height: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<GetGridResponse> {
+        grid_page(deps, canvas_id, Some(height), start_after, limit)
+    }
+
+    pub fn get_point_at(
+        deps: Deps,
+        canvas_id: u32,
+        x: u16,
+        y: u16,
+        height: u64,
+    ) -> StdResult<GetPointAtResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let chunk = grid::load_chunk_at(deps.storage, canvas_id, grid_len, idx / grid::CHUNK_SIZE, height)?;
+        let start = (idx % grid::CHUNK_SIZE) as usize * grid::Z_LEN;
+        let painter = PAINTERS.may_load_at_height(deps.storage, (canvas_id, idx), height.saturating_add(1))?;
+        Ok(GetPointAtResponse {
+            point: chunk[start..start + grid::Z_LEN].to_string(),
+            painter,
+        })
+    }
+
+    // Loads a page of chunks, either current or as of the end of block `height`
+    fn grid_page(
+        deps: Deps,
+        canvas_id: u32,
+        height: Option<u64>,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    ) -> StdResult<GetGridResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let num_chunks = grid::num_chunks(grid_len);
+        let start = start_after.map_or(0, |chunk| chunk.saturating_add(1)).min(num_chunks);
+        let limit = limit.unwrap_or(grid::MAX_GRID_CHUNKS).clamp(1, grid::MAX_GRID_CHUNKS);
+        let end = start.saturating_add(limit).min(num_chunks);
+        let mut z_values = String::new();
+        for chunk in start..end {
+            let z_chunk = match height {
+                Some(height) => grid::load_chunk_at(deps.storage, canvas_id, grid_len, chunk, height)?,
+                None => grid::load_chunk(deps.storage, canvas_id, grid_len, chunk)?,
+            };
+            z_values.push_str(&z_chunk);
+        }
         Ok(GetGridResponse {
             x_size: state.x_size,
             y_size: state.y_size,
-            z_values: state.z_values,
+            offset: start * grid::CHUNK_SIZE,
+            z_values,
+            next_start_after: if end < num_chunks { Some(end - 1) } else { None },
         })
     }
 
-    pub fn get_cost(deps: Deps, x: u8, y: u8) -> StdResult<GetCostResponse> {
-        let state = STATE.load(deps.storage)?;
-        let idx = (y as usize) * (state.x_size as usize) + (x as usize);
-        let update_count = state.update_counts[idx];
-        let num_set_points = state.set_points.iter().map(|byte| byte.count_ones() as usize).sum();
-        let supply_curve_cost = super::execute::bonding_curve(
-            state.supply_base_fee,
-            state.supply_fee_factor as f64 / state.fee_factor_scale as f64,
-            num_set_points,
-        );
-        let update_curve_cost = super::execute::bonding_curve(
-            state.update_base_fee,
-            state.update_fee_factor as f64 / state.fee_factor_scale as f64,
-            update_count as usize,
-        );
-        let set_point_cost = supply_curve_cost + update_curve_cost;
+    pub fn get_region(
+        deps: Deps,
+        canvas_id: u32,
+        x0: u16,
+        y0: u16,
+        width: u16,
+        height: u16,
+    ) -> StdResult<GetRegionResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let region = Region { x0, y0, width, height };
+        let z_values = grid::load_region(deps.storage, canvas_id, state.x_size, state.y_size, &region)?;
+        Ok(GetRegionResponse {
+            x0,
+            y0,
+            width,
+            height,
+            z_values,
+        })
+    }
+
+    /// Loads the region to render, defaulting to the whole grid, and checks the scale
+    fn render_region(
+        deps: Deps,
+        canvas_id: u32,
+        scale: Option<u32>,
+        region: Option<Region>,
+    ) -> StdResult<(String, Region, u32)> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let region = region.unwrap_or(Region {
+            x0: 0,
+            y0: 0,
+            width: state.x_size,
+            height: state.y_size,
+        });
+        let scale = scale.unwrap_or(1);
+        if scale == 0 || scale > render::MAX_SCALE {
+            return Err(StdError::generic_err("Invalid scale"));
+        }
+        let z_values = grid::load_region(deps.storage, canvas_id, state.x_size, state.y_size, &region)?;
+        Ok((z_values, region, scale))
+    }
+
+    pub fn render_svg(
+        deps: Deps,
+        canvas_id: u32,
+        scale: Option<u32>,
+        region: Option<Region>,
+    ) -> StdResult<RenderSvgResponse> {
+        let (z_values, region, scale) = render_region(deps, canvas_id, scale, region)?;
+        Ok(RenderSvgResponse {
+            svg: render::svg(&z_values, region.width, region.height, scale)?,
+        })
+    }
+
+    pub fn render_image(
+        deps: Deps,
+        canvas_id: u32,
+        format: ImageFormat,
+        scale: Option<u32>,
+        region: Option<Region>,
+    ) -> StdResult<RenderImageResponse> {
+        let (z_values, region, scale) = render_region(deps, canvas_id, scale, region)?;
+        let width = region.width as u32 * scale;
+        if width as u64 * (region.height as u32 * scale) as u64 > render::MAX_IMAGE_PIXELS as u64 {
+            return Err(StdError::generic_err("Image too large"));
+        }
+        let rows = render::rgb_rows(&z_values, region.width, region.height, scale);
+        let (image, mime) = match format {
+            ImageFormat::Bmp => (render::bmp(&rows, width), "image/bmp"),
+            ImageFormat::Png => (render::png(&rows, width), "image/png"),
+        };
+        let image = Binary::from(image);
+        Ok(RenderImageResponse {
+            data_uri: format!("data:{};base64,{}", mime, image.to_base64()),
+            image,
+        })
+    }
+
+    pub fn get_row(deps: Deps, canvas_id: u32, y: u16) -> StdResult<GetRowResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let row = Region {
+            x0: 0,
+            y0: y,
+            width: state.x_size,
+            height: 1,
+        };
+        let z_values = grid::load_region(deps.storage, canvas_id, state.x_size, state.y_size, &row)?;
+        Ok(GetRowResponse { y, z_values })
+    }
+
+    pub fn get_points(
+        deps: Deps,
+        canvas_id: u32,
+        coords: Vec<(u16, u16)>,
+    ) -> StdResult<GetPointsResponse> {
+        if coords.len() > grid::MAX_POINTS {
+            return Err(StdError::generic_err("Too many points"));
+        }
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        // Points often share chunks, so load each chunk only once
+        let mut chunks: BTreeMap<u32, String> = BTreeMap::new();
+        let mut points = Vec::with_capacity(coords.len());
+        for (x, y) in coords {
+            let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+            let z_chunk = match chunks.entry(idx / grid::CHUNK_SIZE) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let chunk = *entry.key();
+                    entry.insert(grid::load_chunk(deps.storage, canvas_id, grid_len, chunk)?)
+                }
+            };
+            let start = (idx % grid::CHUNK_SIZE) as usize * grid::Z_LEN;
+            points.push(z_chunk[start..start + grid::Z_LEN].to_string());
+        }
+        Ok(GetPointsResponse { points })
+    }
+
+    pub fn get_cost(deps: Deps, canvas_id: u32, x: u16, y: u16) -> StdResult<GetCostResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+        let update_count = UPDATE_COUNTS
+            .may_load(deps.storage, (canvas_id, idx))?
+            .unwrap_or_default();
+        let supply_cost = super::execute::supply_curve_cost(&state);
+        let update_cost = super::execute::update_curve_cost(&state, update_count);
         Ok(GetCostResponse {
-            cost: set_point_cost,
+            cost: supply_cost.saturating_add(update_cost),
+            supply_cost,
+            update_cost,
+            denom: state.fee_denom,
+            num_set: state.num_set,
+            update_count,
+        })
+    }
+
+    pub fn get_price_ladder(
+        deps: Deps,
+        canvas_id: u32,
+        x: u16,
+        y: u16,
+        steps: u32,
+    ) -> StdResult<GetPriceLadderResponse> {
+        let mut state = canvas::load(deps.storage, canvas_id)?;
+        let idx = grid::point_index(state.x_size, state.y_size, x, y).ok_or_else(grid::out_of_bounds)?;
+        let update_count = UPDATE_COUNTS
+            .may_load(deps.storage, (canvas_id, idx))?
+            .unwrap_or_default();
+        let steps = steps.clamp(1, MAX_LADDER_STEPS);
+        let grid_len = grid::grid_len(state.x_size, state.y_size);
+        let start = state.num_set;
+        let mut supply_prices = Vec::with_capacity(steps as usize);
+        let mut update_prices = Vec::with_capacity(steps as usize);
+        for step in 0..steps {
+            // num_set stops growing once every point is set
+            state.num_set = start.saturating_add(step).min(grid_len);
+            supply_prices.push(super::execute::supply_curve_cost(&state));
+            update_prices.push(super::execute::update_curve_cost(&state, update_count.saturating_add(step)));
+        }
+        Ok(GetPriceLadderResponse {
+            denom: state.fee_denom,
+            supply_prices,
+            update_prices,
         })
     }
 
-    pub fn get_params(deps: Deps) -> StdResult<GetParamsResponse> {
-        let state = STATE.load(deps.storage)?;
+    pub fn get_params(deps: Deps, canvas_id: u32) -> StdResult<GetParamsResponse> {
+        let state = canvas::load(deps.storage, canvas_id)?;
         Ok(GetParamsResponse {
             supply_base_fee: state.supply_base_fee,
             supply_fee_factor: state.supply_fee_factor,
@@ -237,6 +662,8 @@ pub mod query {
             update_fee_factor: state.update_fee_factor,
             fee_factor_scale: state.fee_factor_scale,
             fee_denom: state.fee_denom.clone(),
+            max_update_multiplier: state.max_update_multiplier,
+            num_set: state.num_set,
         })
     }
 }
@@ -14,8 +14,22 @@ pub enum ContractError {
     IndexOutOfBounds {},
     #[error("Invalid z value, must be a 6 character hex string")]
     InvalidZValue {},
+    #[error("Invalid z value, must be a 6 (RGB) or 8 (RGBA) character hex string")]
+    InvalidPaint {},
+    #[error("Sender is not eligible to paint")]
+    NotEligible {},
+    #[error("Invalid merkle root, must be a 32 byte hex string")]
+    InvalidMerkleRoot {},
+    #[error("Access policy is not an allowlist")]
+    NotAllowlist {},
     #[error("Insufficient funds")]
     InsufficientFunds {},
+    #[error("Canvas is still being initialized")]
+    InitPending {},
+    #[error("Canvas is already initialized")]
+    InitFinalized {},
+    #[error("Invalid layer: {reason}")]
+    InvalidLayer { reason: String },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
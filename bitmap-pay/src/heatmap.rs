@@ -0,0 +1,90 @@
+use cosmwasm_std::{Binary, Deps, Env, Order, StdError, StdResult};
+use cw_storage_plus::{Bound, Map};
+
+use crate::canvas;
+use crate::grid;
+use crate::msg::{GetHeatmapResponse, Region};
+use crate::state::{LAST_PAINTED, UPDATE_COUNTS};
+
+/// Reads the values of a Map keyed by canvas_id and grid index for a rectangle,
+/// row by row. Points without an entry are 0.
+fn load_values<T>(
+    deps: Deps,
+    map: &Map<(u32, u32), T>,
+    canvas_id: u32,
+    x_size: u16,
+    region: &Region,
+    value: impl Fn(T) -> u64,
+) -> StdResult<Vec<u64>>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let width = region.width as usize;
+    let mut values = vec![0u64; width * region.height as usize];
+    for (row, y) in (region.y0..region.y0 + region.height).enumerate() {
+        let start = (y as u32) * (x_size as u32) + (region.x0 as u32);
+        let end = start + region.width as u32;
+        // Only points that were set have an entry, so a range skips the rest
+        for item in map.prefix(canvas_id).range(
+            deps.storage,
+            Some(Bound::inclusive(start)),
+            Some(Bound::exclusive(end)),
+            Order::Ascending,
+        ) {
+            let (idx, v) = item?;
+            values[row * width + (idx - start) as usize] = value(v);
+        }
+    }
+    Ok(values)
+}
+
+/// Returns the update count of each point of the region, or with recency_bucket,
+/// how recently each point was painted: 0 for never, 1 within the last
+/// recency_bucket blocks, 2 within the bucket before that, and so on.
+pub fn get_heatmap(
+    deps: Deps,
+    env: Env,
+    canvas_id: u32,
+    region: Option<Region>,
+    recency_bucket: Option<u64>,
+) -> StdResult<GetHeatmapResponse> {
+    let state = canvas::load(deps.storage, canvas_id)?;
+    let region = region.unwrap_or(Region {
+        x0: 0,
+        y0: 0,
+        width: state.x_size,
+        height: state.y_size,
+    });
+    if region.x0 as u32 + region.width as u32 > state.x_size as u32
+        || region.y0 as u32 + region.height as u32 > state.y_size as u32
+    {
+        return Err(grid::out_of_bounds());
+    }
+    if (region.width as u32) * (region.height as u32) > grid::MAX_REGION_POINTS {
+        return Err(StdError::generic_err("Region too large"));
+    }
+    let values = match recency_bucket {
+        None => load_values(deps, &UPDATE_COUNTS, canvas_id, state.x_size, &region, u64::from)?,
+        Some(0) => return Err(StdError::generic_err("Invalid recency bucket")),
+        Some(bucket) => load_values(deps, &LAST_PAINTED, canvas_id, state.x_size, &region, |height| {
+            1 + env.block.height.saturating_sub(height) / bucket
+        })?,
+    };
+    let max_value = values.iter().copied().max().unwrap_or_default();
+    let bytes_per_cell: u8 = match max_value {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xffff_ffff => 4,
+        _ => 8,
+    };
+    let mut data = Vec::with_capacity(values.len() * bytes_per_cell as usize);
+    for value in values {
+        data.extend_from_slice(&value.to_be_bytes()[8 - bytes_per_cell as usize..]);
+    }
+    Ok(GetHeatmapResponse {
+        region,
+        bytes_per_cell,
+        max_value,
+        data: Binary::from(data),
+    })
+}
@@ -0,0 +1,67 @@
+use cosmwasm_std::{Addr, DepsMut, Env, MessageInfo, Response, Storage};
+
+use crate::analytics;
+use crate::canvas;
+use crate::error::ContractError;
+use crate::grid;
+use crate::layers;
+use crate::state::INIT_CREATOR;
+
+/// Rejects Set until the creator has sent FinalizeInit
+pub fn ensure_open(storage: &dyn Storage, canvas_id: u32) -> Result<(), ContractError> {
+    if INIT_CREATOR.has(storage, canvas_id) {
+        return Err(ContractError::InitPending {});
+    }
+    Ok(())
+}
+
+fn ensure_creator(storage: &dyn Storage, canvas_id: u32, sender: &Addr) -> Result<(), ContractError> {
+    match INIT_CREATOR.may_load(storage, canvas_id)? {
+        Some(creator) if creator == *sender => Ok(()),
+        Some(_) => Err(ContractError::Unauthorized {}),
+        None => Err(ContractError::InitFinalized {}),
+    }
+}
+
+pub fn init_chunk(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    canvas_id: u32,
+    offset: u32,
+    data: String,
+) -> Result<Response, ContractError> {
+    let state = canvas::load(deps.storage, canvas_id)?;
+    layers::ensure_not_layer(deps.storage, canvas_id)?;
+    ensure_creator(deps.storage, canvas_id, &info.sender)?;
+    let len = data.len() / grid::Z_LEN;
+    if len == 0 || data.len() != len * grid::Z_LEN || !grid::is_hex(&data) {
+        return Err(ContractError::InvalidZValue {});
+    }
+    let grid_len = grid::grid_len(state.x_size, state.y_size);
+    if offset as u64 + len as u64 > grid_len as u64 {
+        return Err(ContractError::IndexOutOfBounds {});
+    }
+    // The histogram needs the values being replaced, since chunks can be sent again
+    let old_data = grid::load_range(deps.storage, canvas_id, grid_len, offset, len as u32)?;
+    analytics::update_colors(deps.storage, canvas_id, Some(&old_data), &data)?;
+    grid::save_range(deps.storage, canvas_id, grid_len, offset, &data, env.block.height)?;
+    Ok(Response::new()
+        .add_attribute("action", "init_chunk")
+        .add_attribute("canvas_id", canvas_id.to_string())
+        .add_attribute("offset", offset.to_string())
+        .add_attribute("points", len.to_string()))
+}
+
+pub fn finalize_init(
+    deps: DepsMut,
+    info: MessageInfo,
+    canvas_id: u32,
+) -> Result<Response, ContractError> {
+    canvas::load(deps.storage, canvas_id)?;
+    ensure_creator(deps.storage, canvas_id, &info.sender)?;
+    INIT_CREATOR.remove(deps.storage, canvas_id);
+    Ok(Response::new()
+        .add_attribute("action", "finalize_init")
+        .add_attribute("canvas_id", canvas_id.to_string()))
+}
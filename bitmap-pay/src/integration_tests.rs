@@ -35,14 +35,12 @@ mod tests {
         })
     }
 
-    use crate::msg::{QueryMsg, GetPointResponse, GetGridResponse};
+    use crate::msg::{ExecuteMsg, GetCostResponse, GetGridResponse, GetPointResponse, QueryMsg};
 
-    fn proper_instantiate(x_size: u8, y_size: u8) -> (App, CwTemplateContract) {
-        let mut app = mock_app();
-        let cw_template_id = app.store_code(contract_template());
+    fn proper_instantiate(x_size: u16, y_size: u16) -> (App, CwTemplateContract) {
         let grid_len = (x_size as usize) * (y_size as usize) * 6;
-        let nonzero_z = "aabbcc".repeat((grid_len + 5) / 6).chars().take(grid_len).collect::<String>();
-        let msg = InstantiateMsg {
+        let nonzero_z = "aabbcc".repeat(grid_len.div_ceil(6)).chars().take(grid_len).collect::<String>();
+        instantiate_with(InstantiateMsg {
             x_size,
             y_size,
             z_values: Some(nonzero_z.clone()),
@@ -53,7 +51,16 @@ mod tests {
             update_fee_factor: 10,
             fee_factor_scale: 100,
             fee_denom: NATIVE_DENOM.to_string(),
-        };
+            max_update_multiplier: None,
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        })
+    }
+
+    fn instantiate_with(msg: InstantiateMsg) -> (App, CwTemplateContract) {
+        let mut app = mock_app();
+        let cw_template_id = app.store_code(contract_template());
         let cw_template_contract_addr = app
             .instantiate_contract(
                 cw_template_id,
@@ -68,13 +75,54 @@ mod tests {
         (app, cw_template_contract)
     }
 
+    /// Creates a canvas with no fees next to the default one and returns its canvas_id
+    fn create_free_canvas(
+        app: &mut App,
+        cw_template_contract: &CwTemplateContract,
+        x_size: u16,
+        y_size: u16,
+        chunked_init: Option<bool>,
+    ) -> u32 {
+        let config = crate::msg::CanvasConfig {
+            x_size,
+            y_size,
+            z_values: None,
+            recipient: ADMIN.to_string(),
+            supply_base_fee: 0,
+            supply_fee_factor: 0,
+            update_base_fee: 0,
+            update_fee_factor: 0,
+            fee_factor_scale: 1,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: None,
+            admin: None,
+            access_policy: None,
+            chunked_init,
+        };
+        let res = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.addr(),
+                &ExecuteMsg::CreateCanvas { config: Box::new(config) },
+                &[],
+            )
+            .unwrap();
+        let canvas_id = res
+            .events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "canvas_id")
+            .unwrap();
+        canvas_id.value.parse().unwrap()
+    }
+
     #[test]
     fn test_instantiate() {
         let (app, cw_template_contract) = proper_instantiate(2, 2);
         // Query grid and check all zeroes
         let res: GetGridResponse = app.wrap().query_wasm_smart(
             cw_template_contract.addr(),
-            &QueryMsg::GetGrid {},
+            &QueryMsg::GetGrid { canvas_id: None, start_after: None, limit: None },
         ).unwrap();
     assert_eq!(res.x_size, 2);
     assert_eq!(res.y_size, 2);
@@ -83,9 +131,1163 @@ mod tests {
 
     let res: GetPointResponse = app.wrap().query_wasm_smart(
         cw_template_contract.addr(),
-        &QueryMsg::GetPoint { x: 1, y: 1 },
+        &QueryMsg::GetPoint { canvas_id: None, x: 1, y: 1 },
     ).unwrap();
     assert_eq!(res.point, "aabbcc".to_string());
     }
 
+    #[test]
+    fn test_update_count_past_255_with_capped_price() {
+        let (mut app, cw_template_contract) = instantiate_with(InstantiateMsg {
+            x_size: 2,
+            y_size: 2,
+            z_values: None,
+            recipient: ADMIN.to_string(),
+            supply_base_fee: 0,
+            supply_fee_factor: 0,
+            update_base_fee: 10,
+            update_fee_factor: 100,
+            fee_factor_scale: 100,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: Some(5),
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        });
+        let user = MockApi::default().addr_make(USER);
+        for _ in 0..300 {
+            let cost: GetCostResponse = app.wrap().query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetCost { canvas_id: None, x: 0, y: 0 },
+            ).unwrap();
+            assert!(cost.cost <= 50);
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x: 0, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[Coin { denom: NATIVE_DENOM.to_string(), amount: Uint128::new(cost.cost) }],
+            ).unwrap();
+        }
+        let res: GetPointResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPoint { canvas_id: None, x: 0, y: 0 },
+        ).unwrap();
+        assert_eq!(res.update_count, 300);
+        let cost: GetCostResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetCost { canvas_id: None, x: 0, y: 0 },
+        ).unwrap();
+        assert_eq!(cost.cost, 50);
+    }
+
+    #[test]
+    fn test_set_across_chunks() {
+        let (mut app, cw_template_contract) = instantiate_with(InstantiateMsg {
+            x_size: 1024,
+            y_size: 1024,
+            z_values: None,
+            recipient: ADMIN.to_string(),
+            supply_base_fee: 10,
+            supply_fee_factor: 0,
+            update_base_fee: 10,
+            update_fee_factor: 0,
+            fee_factor_scale: 100,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: None,
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        });
+        let user = MockApi::default().addr_make(USER);
+        let funds = [Coin { denom: NATIVE_DENOM.to_string(), amount: Uint128::new(20) }];
+        for (x, y) in [(1023, 0), (0, 1), (1023, 1023), (1023, 1023)] {
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x, y, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+                &funds,
+            ).unwrap();
+        }
+        app.execute_contract(
+            user,
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set { canvas_id: None, layer: None, x: 0, y: 1024, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+            &funds,
+        ).unwrap_err();
+
+        let res: GetPointResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPoint { canvas_id: None, x: 1023, y: 1023 },
+        ).unwrap();
+        assert_eq!(res.point, "112233");
+        assert!(res.is_set);
+        assert_eq!(res.update_count, 2);
+        let res: GetPointResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetPoint { canvas_id: None, x: 1022, y: 1023 },
+        ).unwrap();
+        assert_eq!(res.point, "000000");
+        assert!(!res.is_set);
+
+        let res: GetGridResponse = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetGrid { canvas_id: None, start_after: Some(1022), limit: Some(5) },
+        ).unwrap();
+        assert_eq!(res.offset, 1023 * 1024);
+        assert_eq!(res.next_start_after, None);
+        assert_eq!(&res.z_values[res.z_values.len() - 6..], "112233");
+    }
+
+    #[test]
+    fn test_cost_breakdown_and_price_ladder() {
+        use crate::contract::execute::bonding_curve;
+        use crate::msg::GetPriceLadderResponse;
+        let (mut app, cw_template_contract) = instantiate_with(InstantiateMsg {
+            x_size: 2,
+            y_size: 1,
+            z_values: None,
+            recipient: ADMIN.to_string(),
+            supply_base_fee: 100,
+            supply_fee_factor: 50,
+            update_base_fee: 10,
+            update_fee_factor: 100,
+            fee_factor_scale: 100,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: Some(5),
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        });
+        let cost = |app: &App| -> GetCostResponse {
+            app.wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCost { canvas_id: None, x: 0, y: 0 })
+                .unwrap()
+        };
+        let res = cost(&app);
+        assert_eq!((res.supply_cost, res.update_cost, res.cost), (100, 10, 110));
+        assert_eq!((res.denom.as_str(), res.num_set, res.update_count), (NATIVE_DENOM, 0, 0));
+
+        app.execute_contract(
+            MockApi::default().addr_make(USER),
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set { canvas_id: None, layer: None, x: 0, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+            &[Coin { denom: NATIVE_DENOM.to_string(), amount: Uint128::new(res.cost) }],
+        )
+        .unwrap();
+        let res = cost(&app);
+        assert_eq!((res.num_set, res.update_count), (1, 1));
+        assert_eq!(res.supply_cost, bonding_curve(100, 0.5, 1));
+        assert_eq!(res.update_cost, bonding_curve(10, 1.0, 1));
+        assert_eq!(res.cost, res.supply_cost + res.update_cost);
+
+        let res: GetPriceLadderResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPriceLadder { canvas_id: None, x: 0, y: 0, steps: 4 })
+            .unwrap();
+        // The supply curve stops once both points are set, the update curve at 5x its base fee
+        let supply = bonding_curve(100, 0.5, 1);
+        let full = bonding_curve(100, 0.5, 2);
+        assert_eq!(res.supply_prices, vec![supply, full, full, full]);
+        assert_eq!(
+            res.update_prices,
+            vec![bonding_curve(10, 1.0, 1), 50, 50, 50]
+        );
+        let res: GetPriceLadderResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPriceLadder { canvas_id: None, x: 0, y: 0, steps: 1000 })
+            .unwrap();
+        assert_eq!(res.supply_prices.len(), 100);
+    }
+
+    #[test]
+    fn test_treasury() {
+        use crate::msg::GetTreasuryResponse;
+        use cosmwasm_std::BankMsg;
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let user = MockApi::default().addr_make(USER);
+        let treasury = |app: &App| -> GetTreasuryResponse {
+            app.wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetTreasury {})
+                .unwrap()
+        };
+        let res = treasury(&app);
+        assert_eq!((res.paints, res.unique_painters), (0, 0));
+        assert_eq!(res.denoms.len(), 1);
+        assert_eq!(res.denoms[0].balance, Uint128::zero());
+
+        // Overpay twice, the excess stays in the contract
+        let mut revenue = 0;
+        for x in 0..2 {
+            let cost: GetCostResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCost { canvas_id: None, x, y: 0 })
+                .unwrap();
+            revenue += cost.cost;
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[Coin { denom: NATIVE_DENOM.to_string(), amount: Uint128::new(cost.cost + 50) }],
+            )
+            .unwrap();
+        }
+        // Funds sent outside of Set are reported as unrecorded
+        app.execute(
+            user.clone(),
+            BankMsg::Send {
+                to_address: cw_template_contract.addr().to_string(),
+                amount: vec![Coin::new(7u128, NATIVE_DENOM)],
+            }
+            .into(),
+        )
+        .unwrap();
+        let res = treasury(&app);
+        assert_eq!((res.paints, res.unique_painters), (2, 1));
+        let denom = &res.denoms[0];
+        assert_eq!(denom.revenue, Uint128::new(revenue));
+        assert_eq!((denom.retained, denom.expected_balance), (Uint128::new(100), Uint128::new(100)));
+        assert_eq!((denom.balance, denom.unrecorded), (Uint128::new(107), Uint128::new(7)));
+
+        let withdraw = |amount: u128| ExecuteMsg::Withdraw {
+            denom: NATIVE_DENOM.to_string(),
+            amount: Uint128::new(amount),
+            to: MockApi::default().addr_make("treasurer").to_string(),
+        };
+        let err = app
+            .execute_contract(user, cw_template_contract.addr(), &withdraw(30), &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        let err = app
+            .execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &withdraw(108), &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Insufficient funds");
+        app.execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &withdraw(30), &[])
+            .unwrap();
+        let balance = app
+            .wrap()
+            .query_balance(MockApi::default().addr_make("treasurer"), NATIVE_DENOM)
+            .unwrap();
+        assert_eq!(balance.amount, Uint128::new(30));
+        let denom = treasury(&app).denoms.remove(0);
+        assert_eq!((denom.withdrawn, denom.expected_balance), (Uint128::new(30), Uint128::new(70)));
+        assert_eq!((denom.balance, denom.unrecorded), (Uint128::new(77), Uint128::new(7)));
+    }
+
+    #[test]
+    fn test_analytics() {
+        use crate::msg::{
+            GetColorHistogramResponse, GetStatsResponse, GetTopPaintersResponse, PainterRanking,
+        };
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let user = MockApi::default().addr_make(USER);
+        let other = MockApi::default().addr_make("other");
+        app.send_tokens(user.clone(), other.clone(), &[Coin::new(100_000u128, NATIVE_DENOM)])
+            .unwrap();
+        let set = |app: &mut App, sender: &Addr, x: u16, z: &str| -> u128 {
+            let cost: GetCostResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCost { canvas_id: None, x, y: 0 })
+                .unwrap();
+            app.execute_contract(
+                sender.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x, y: 0, z: z.to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[Coin::new(cost.cost, NATIVE_DENOM)],
+            )
+            .unwrap();
+            cost.cost
+        };
+        let user_spent = set(&mut app, &user, 0, "112233") + set(&mut app, &user, 1, "112233");
+        let other_spent = set(&mut app, &other, 0, "FFFFFF") + set(&mut app, &other, 0, "ffffff");
+
+        let stats: GetStatsResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetStats { canvas_id: None })
+            .unwrap();
+        assert_eq!((stats.paints, stats.pixels_set, stats.unique_painters), (4, 2, 2));
+        assert_eq!(stats.total_revenue, Coin::new(user_spent + other_spent, NATIVE_DENOM));
+        let most = stats.most_painted.unwrap();
+        assert_eq!((most.x, most.y, most.update_count), (0, 0, 3));
+
+        // Both painters hold one pixel, other spent more
+        let top = |app: &App, by: PainterRanking| -> GetTopPaintersResponse {
+            app.wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetTopPainters { canvas_id: None, by, limit: Some(1) })
+                .unwrap()
+        };
+        assert!(other_spent > user_spent);
+        let res = top(&app, PainterRanking::AmountSpent);
+        assert_eq!(res.painters.len(), 1);
+        assert_eq!(res.painters[0].painter, other);
+        assert_eq!(res.painters[0].amount_spent, Coin::new(other_spent, NATIVE_DENOM));
+        assert_eq!((res.painters[0].pixels_held, res.painters[0].paints), (1, 2));
+        set(&mut app, &user, 0, "112233");
+        let res = top(&app, PainterRanking::PixelsHeld);
+        assert_eq!((&res.painters[0].painter, res.painters[0].pixels_held), (&user, 2));
+
+        // The grid started as aabbcc, two points are now 112233
+        let res: GetColorHistogramResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetColorHistogram { canvas_id: None, start_after: None, limit: None },
+            )
+            .unwrap();
+        let colors: Vec<_> = res.colors.iter().map(|c| (c.color.as_str(), c.count)).collect();
+        assert_eq!(colors, vec![("112233", 2), ("aabbcc", 2)]);
+        let res: GetColorHistogramResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetColorHistogram { canvas_id: None, start_after: Some("112233".to_string()), limit: Some(1) },
+            )
+            .unwrap();
+        assert_eq!(res.colors[0].color, "aabbcc");
+    }
+
+    #[test]
+    fn test_heatmap() {
+        use crate::msg::{GetHeatmapResponse, Region};
+        let (mut app, cw_template_contract) = proper_instantiate(3, 2);
+        let user = MockApi::default().addr_make(USER);
+        for (x, y) in [(1, 0), (1, 0), (2, 1)] {
+            let cost: GetCostResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCost { canvas_id: None, x, y })
+                .unwrap();
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x, y, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[Coin::new(cost.cost, NATIVE_DENOM)],
+            )
+            .unwrap();
+            app.update_block(|block| block.height += 10);
+        }
+        let heatmap = |app: &App, region: Option<Region>, recency_bucket: Option<u64>| {
+            app.wrap().query_wasm_smart::<GetHeatmapResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::GetHeatmap { canvas_id: None, region, recency_bucket },
+            )
+        };
+        let res = heatmap(&app, None, None).unwrap();
+        assert_eq!((res.bytes_per_cell, res.max_value), (1, 2));
+        assert_eq!(res.data.to_vec(), vec![0, 2, 0, 0, 0, 1]);
+
+        // (1, 0) was last painted 20 blocks ago, (2, 1) 10 blocks ago
+        let region = Region { x0: 1, y0: 0, width: 2, height: 2 };
+        let res = heatmap(&app, Some(region.clone()), Some(5)).unwrap();
+        assert_eq!(res.data.to_vec(), vec![5, 0, 0, 3]);
+        assert_eq!(res.region, region);
+
+        let region = Region { x0: 2, y0: 0, width: 2, height: 1 };
+        heatmap(&app, Some(region), None).unwrap_err();
+        heatmap(&app, None, Some(0)).unwrap_err();
+    }
+
+    #[test]
+    fn test_multiple_canvases() {
+        use crate::msg::{CanvasConfig, GetTopPaintersResponse, ListCanvasesResponse, PainterRanking};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let user = MockApi::default().addr_make(USER);
+        let config = CanvasConfig {
+            x_size: 3,
+            y_size: 1,
+            z_values: None,
+            recipient: MockApi::default().addr_make("artist").to_string(),
+            supply_base_fee: 7,
+            supply_fee_factor: 0,
+            update_base_fee: 3,
+            update_fee_factor: 0,
+            fee_factor_scale: 1,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: None,
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        };
+        let create = ExecuteMsg::CreateCanvas { config: Box::new(config) };
+        let err = app
+            .execute_contract(user.clone(), cw_template_contract.addr(), &create, &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        let res = app
+            .execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &create, &[])
+            .unwrap();
+        let canvas_id = res
+            .events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "canvas_id")
+            .unwrap();
+        assert_eq!(canvas_id.value, "1");
+
+        // The new canvas has its own size and pricing, and pays its own recipient
+        let cost: GetCostResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCost { canvas_id: Some(1), x: 2, y: 0 })
+            .unwrap();
+        assert_eq!(cost.cost, 10);
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set { canvas_id: Some(1), layer: None, x: 2, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+            &[Coin::new(10u128, NATIVE_DENOM)],
+        )
+        .unwrap();
+        let balance = app
+            .wrap()
+            .query_balance(MockApi::default().addr_make("artist"), NATIVE_DENOM)
+            .unwrap();
+        assert_eq!(balance.amount, Uint128::new(10));
+        app.execute_contract(
+            user,
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set { canvas_id: Some(1), layer: None, x: 0, y: 1, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+            &[Coin::new(10u128, NATIVE_DENOM)],
+        )
+        .unwrap_err();
+
+        let point = |canvas_id: Option<u32>| -> GetPointResponse {
+            app.wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { canvas_id, x: 0, y: 0 })
+                .unwrap()
+        };
+        assert_eq!(point(None).point, "aabbcc");
+        assert_eq!(point(Some(0)).point, "aabbcc");
+        assert_eq!(point(Some(1)).point, "000000");
+        let grid: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetGrid { canvas_id: Some(1), start_after: None, limit: None },
+            )
+            .unwrap();
+        assert_eq!(grid.z_values, "000000000000112233");
+
+        let res: ListCanvasesResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::ListCanvases { start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(res.canvases.len(), 2);
+        assert_eq!((res.canvases[1].canvas_id, res.canvases[1].x_size, res.canvases[1].num_set), (1, 3, 1));
+        assert_eq!(res.canvases[0].admin, Addr::unchecked(ADMIN));
+        let res: ListCanvasesResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::ListCanvases { start_after: Some(0), limit: None })
+            .unwrap();
+        assert_eq!(res.canvases.len(), 1);
+
+        // Painters are ranked per canvas, the user has not painted canvas 0
+        let top = |canvas_id: Option<u32>| -> GetTopPaintersResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetTopPainters { canvas_id, by: PainterRanking::AmountSpent, limit: None },
+                )
+                .unwrap()
+        };
+        assert!(top(None).painters.is_empty());
+        let res = top(Some(1));
+        assert_eq!(res.painters.len(), 1);
+        assert_eq!((res.painters[0].pixels_held, &res.painters[0].amount_spent), (1, &Coin::new(10u128, NATIVE_DENOM)));
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<GetPointResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::GetPoint { canvas_id: Some(2), x: 0, y: 0 },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Canvas not found"));
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    enum Cw721QueryMsg {
+        Tokens {
+            owner: String,
+            start_after: Option<String>,
+            limit: Option<u32>,
+        },
+    }
+
+    #[cosmwasm_schema::cw_serde]
+    struct TokensResponse {
+        tokens: Vec<String>,
+    }
+
+    // Minimal cw721 that reports one token for USER and none for everyone else
+    fn mock_cw721() -> Box<dyn Contract<Empty>> {
+        use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError};
+        let execute = |_: DepsMut, _: Env, _: MessageInfo, _: Empty| -> Result<Response, StdError> {
+            Ok(Response::new())
+        };
+        let query = |_: Deps, _: Env, msg: Cw721QueryMsg| -> Result<Binary, StdError> {
+            let Cw721QueryMsg::Tokens { owner, .. } = msg;
+            let tokens = if owner == MockApi::default().addr_make(USER).as_str() {
+                vec!["1".to_string()]
+            } else {
+                vec![]
+            };
+            to_json_binary(&TokensResponse { tokens })
+        };
+        Box::new(ContractWrapper::new(execute, execute, query))
+    }
+
+    #[test]
+    fn test_token_gated_policies() {
+        use crate::msg::AccessPolicy;
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let user = MockApi::default().addr_make(USER);
+        let other = MockApi::default().addr_make("other");
+        app.send_tokens(user.clone(), other.clone(), &[Coin { denom: NATIVE_DENOM.to_string(), amount: Uint128::new(1000) }])
+            .unwrap();
+        let cw721_id = app.store_code(mock_cw721());
+        let collection = app
+            .instantiate_contract(cw721_id, Addr::unchecked(ADMIN), &Empty {}, &[], "nft", None)
+            .unwrap();
+        let set = |app: &mut App, sender: &Addr| {
+            app.execute_contract(
+                sender.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x: 0, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[Coin { denom: NATIVE_DENOM.to_string(), amount: Uint128::new(500) }],
+            )
+        };
+        let set_policy = |app: &mut App, policy: AccessPolicy| {
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.addr(),
+                &ExecuteMsg::SetAccessPolicy { canvas_id: None, policy },
+                &[],
+            )
+            .unwrap();
+        };
+
+        set_policy(&mut app, AccessPolicy::HoldsNativeBalance {
+            denom: NATIVE_DENOM.to_string(),
+            min: Uint128::new(10_000),
+        });
+        set(&mut app, &user).unwrap();
+        let err = set(&mut app, &other).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Sender is not eligible to paint");
+
+        set_policy(&mut app, AccessPolicy::HoldsCw721 { collection: collection.to_string() });
+        set(&mut app, &user).unwrap();
+        set(&mut app, &other).unwrap_err();
+
+        let res: AccessPolicy = app.wrap().query_wasm_smart(
+            cw_template_contract.addr(),
+            &QueryMsg::GetAccessPolicy { canvas_id: None },
+        ).unwrap();
+        assert_eq!(res, AccessPolicy::HoldsCw721 { collection: collection.to_string() });
+    }
+
+    #[test]
+    fn test_trusted_router() {
+        use crate::msg::{AccessPolicy, GetTopPaintersResponse, GetTrustedRouterResponse, PainterRanking};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        // The funded user plays the router, painting for other
+        let router = MockApi::default().addr_make(USER);
+        let other = MockApi::default().addr_make("other");
+        let set = |app: &mut App, sender: &Addr, on_behalf_of: Option<&Addr>| {
+            app.execute_contract(
+                sender.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set {
+                    canvas_id: None,
+                    layer: None,
+                    x: 0,
+                    y: 0,
+                    z: "112233".to_string(),
+                    blend: None,
+                    proof: None,
+                    on_behalf_of: on_behalf_of.map(Addr::to_string),
+                },
+                &[Coin::new(500u128, NATIVE_DENOM)],
+            )
+        };
+        let set_router = ExecuteMsg::SetTrustedRouter { canvas_id: None, router: Some(router.to_string()) };
+
+        let err = set(&mut app, &router, Some(&other)).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        let err = app
+            .execute_contract(router.clone(), cw_template_contract.addr(), &set_router, &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        app.execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &set_router, &[])
+            .unwrap();
+        let res: GetTrustedRouterResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetTrustedRouter { canvas_id: None })
+            .unwrap();
+        assert_eq!(res.router, Some(router.clone()));
+
+        // The access policy and the stats apply to the painter, not to the router
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cw_template_contract.addr(),
+            &ExecuteMsg::SetAccessPolicy { canvas_id: None, policy: AccessPolicy::Allowlist(vec![other.clone()]) },
+            &[],
+        )
+        .unwrap();
+        let err = set(&mut app, &router, None).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Sender is not eligible to paint");
+        set(&mut app, &router, Some(&other)).unwrap();
+        let res: GetTopPaintersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetTopPainters { canvas_id: None, by: PainterRanking::PixelsHeld, limit: None },
+            )
+            .unwrap();
+        assert_eq!(res.painters.len(), 1);
+        assert_eq!(res.painters[0].painter, other);
+
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cw_template_contract.addr(),
+            &ExecuteMsg::SetTrustedRouter { canvas_id: None, router: None },
+            &[],
+        )
+        .unwrap();
+        set(&mut app, &router, Some(&other)).unwrap_err();
+    }
+
+    #[test]
+    fn test_layers() {
+        use crate::msg::{
+            AccessPolicy, GetCompositeResponse, GetLayersResponse, GetParamsResponse, LayerConfig, ListCanvasesResponse,
+            Region,
+        };
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let user = MockApi::default().addr_make(USER);
+        let artist = MockApi::default().addr_make("artist");
+        let layer_config = |base_fee: u128, z_values: Option<String>, access_policy: Option<AccessPolicy>| LayerConfig {
+            z_values,
+            supply_base_fee: base_fee,
+            supply_fee_factor: 0,
+            update_base_fee: base_fee,
+            update_fee_factor: 0,
+            fee_factor_scale: 1,
+            max_update_multiplier: None,
+            access_policy,
+        };
+        let add_layer = |name: &str, transparent: &str, config: LayerConfig| ExecuteMsg::AddLayer {
+            canvas_id: None,
+            name: name.to_string(),
+            transparent: transparent.to_string(),
+            config: Box::new(config),
+        };
+
+        // A background only the artist paints, for free, under a public layer with its own pricing.
+        // The background starts with one opaque point, the public layer is fully transparent.
+        let seed = Some(["ff00ff", "ff00ff", "ff00ff", "123456"].concat());
+        let background = add_layer("background", "FF00FF", layer_config(0, seed, Some(AccessPolicy::Allowlist(vec![artist.clone()]))));
+        let err = app
+            .execute_contract(user.clone(), cw_template_contract.addr(), &background, &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        app.execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &background, &[])
+            .unwrap();
+        let err = app
+            .execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &background, &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid layer: name is already taken");
+        let err = app
+            .execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &add_layer("public", "white", layer_config(5, None, None)), &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid z value, must be a 6 character hex string");
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cw_template_contract.addr(),
+            &add_layer("public", "ffffff", layer_config(5, None, None)),
+            &[],
+        )
+        .unwrap();
+
+        let layers: GetLayersResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetLayers { canvas_id: None })
+            .unwrap();
+        let names: Vec<_> = layers.layers.iter().map(|layer| (layer.name.as_str(), layer.canvas_id, layer.transparent.as_str())).collect();
+        assert_eq!(names, vec![("background", 1, "ff00ff"), ("public", 2, "ffffff")]);
+        // Layers take the size and fee denom of the canvas, and are not listed as canvases
+        let grid: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetGrid { canvas_id: Some(2), start_after: None, limit: None })
+            .unwrap();
+        assert_eq!((grid.x_size, grid.y_size), (2, 2));
+        let params: GetParamsResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetParams { canvas_id: Some(2) })
+            .unwrap();
+        assert_eq!(params.fee_denom, NATIVE_DENOM);
+        let res: ListCanvasesResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::ListCanvases { start_after: None, limit: None })
+            .unwrap();
+        assert_eq!(res.canvases.len(), 1);
+        let composite = |app: &App, region: Option<Region>| -> String {
+            let res: GetCompositeResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetComposite { canvas_id: None, region })
+                .unwrap();
+            res.z_values
+        };
+        assert_eq!(composite(&app, None), ["aabbcc", "aabbcc", "aabbcc", "123456"].concat());
+
+        let set = |layer: &str, x: u16, z: &str| ExecuteMsg::Set {
+            canvas_id: None,
+            layer: Some(layer.to_string()),
+            x,
+            y: 0,
+            z: z.to_string(),
+            blend: None,
+            proof: None,
+            on_behalf_of: None,
+        };
+        // Each layer applies its own access policy and pricing
+        let err = app
+            .execute_contract(user.clone(), cw_template_contract.addr(), &set("background", 0, "445566"), &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Sender is not eligible to paint");
+        app.execute_contract(artist.clone(), cw_template_contract.addr(), &set("background", 0, "445566"), &[])
+            .unwrap();
+        app.execute_contract(artist.clone(), cw_template_contract.addr(), &set("background", 1, "778899"), &[])
+            .unwrap();
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &set("public", 1, "112233"),
+            &[Coin::new(10u128, NATIVE_DENOM)],
+        )
+        .unwrap();
+        // The cost goes to the recipient of the canvas, not to the artist
+        assert!(app.wrap().query_balance(&artist, NATIVE_DENOM).unwrap().amount.is_zero());
+        let err = app
+            .execute_contract(user.clone(), cw_template_contract.addr(), &set("missing", 0, "112233"), &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Generic error: Layer not found");
+        // A layer is only painted through its canvas
+        let direct = ExecuteMsg::Set { canvas_id: Some(2), layer: None, x: 0, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None };
+        let err = app
+            .execute_contract(user.clone(), cw_template_contract.addr(), &direct, &[Coin::new(10u128, NATIVE_DENOM)])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid layer: set a layer through the layer argument of its canvas");
+        let init = ExecuteMsg::InitChunk { canvas_id: Some(2), offset: 0, data: "112233".to_string() };
+        let err = app
+            .execute_contract(Addr::unchecked(ADMIN), cw_template_contract.addr(), &init, &[])
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid layer: set a layer through the layer argument of its canvas");
+
+        // The topmost opaque layer wins, the base shows through transparent points
+        assert_eq!(composite(&app, None), ["445566", "112233", "aabbcc", "123456"].concat());
+        let region = Region { x0: 1, y0: 0, width: 1, height: 2 };
+        assert_eq!(composite(&app, Some(region)), "112233123456");
+        // Black is an ordinary color on a layer, and painting the marker clears a point again
+        let paint_public = |app: &mut App, z: &str| {
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &set("public", 0, z),
+                &[Coin::new(10u128, NATIVE_DENOM)],
+            )
+            .unwrap();
+        };
+        paint_public(&mut app, "000000");
+        assert_eq!(composite(&app, None), ["000000", "112233", "aabbcc", "123456"].concat());
+        paint_public(&mut app, "ffffff");
+        assert_eq!(composite(&app, None), ["445566", "112233", "aabbcc", "123456"].concat());
+        let point: GetPointResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { canvas_id: Some(1), x: 1, y: 0 })
+            .unwrap();
+        assert_eq!(point.point, "778899");
+
+        // Layers do not nest
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.addr(),
+                &ExecuteMsg::AddLayer {
+                    canvas_id: Some(1),
+                    name: "nested".to_string(),
+                    transparent: "000000".to_string(),
+                    config: Box::new(layer_config(0, None, None)),
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Invalid layer: a layer cannot have layers");
+    }
+
+    #[test]
+    fn test_blend() {
+        use crate::msg::{BlendMode, GetColorHistogramResponse};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let user = MockApi::default().addr_make(USER);
+        let cost: GetCostResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetCost { canvas_id: None, x: 0, y: 0 })
+            .unwrap();
+        let set = |app: &mut App, z: &str, blend: Option<BlendMode>| {
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id: None, layer: None, x: 0, y: 0, z: z.to_string(), blend, proof: None, on_behalf_of: None },
+                &[Coin::new(cost.cost * 10, NATIVE_DENOM)],
+            )
+        };
+        let err = set(&mut app, "ffffff8", Some(BlendMode::AlphaOver)).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Invalid z value, must be a 6 (RGB) or 8 (RGBA) character hex string"
+        );
+
+        // Half transparent white over "aabbcc", stored and counted as the mixed color
+        set(&mut app, "ffffff80", Some(BlendMode::AlphaOver)).unwrap();
+        let point: GetPointResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { canvas_id: None, x: 0, y: 0 })
+            .unwrap();
+        assert_eq!(point.point, "d5dde6");
+        let histogram: GetColorHistogramResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetColorHistogram { canvas_id: None, start_after: None, limit: None },
+            )
+            .unwrap();
+        let counts: Vec<_> = histogram.colors.iter().map(|c| (c.color.as_str(), c.count)).collect();
+        assert_eq!(counts, vec![("aabbcc", 3), ("d5dde6", 1)]);
+    }
+
+    #[test]
+    fn test_region_row_and_points() {
+        use crate::msg::{GetPointsResponse, GetRegionResponse, GetRowResponse};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        // 40x40 grid spans two chunks, split between (23, 25) and (24, 25)
+        let canvas_id = Some(create_free_canvas(&mut app, &cw_template_contract, 40, 40, None));
+        let user = MockApi::default().addr_make(USER);
+        for (x, z) in [(23, "112233"), (24, "445566")] {
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id, layer: None, x, y: 25, z: z.to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[],
+            )
+            .unwrap();
+        }
+
+        let res: GetRegionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetRegion { canvas_id, x0: 22, y0: 25, width: 4, height: 2 },
+            )
+            .unwrap();
+        assert_eq!((res.x0, res.y0, res.width, res.height), (22, 25, 4, 2));
+        assert_eq!(res.z_values, format!("000000112233445566000000{}", "000000".repeat(4)));
+
+        let res: GetRowResponse = app
+            .wrap()
+            .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetRow { canvas_id, y: 25 })
+            .unwrap();
+        assert_eq!(res.y, 25);
+        assert_eq!(res.z_values.len(), 40 * 6);
+        assert_eq!(&res.z_values[23 * 6..25 * 6], "112233445566");
+
+        let res: GetPointsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetPoints { canvas_id, coords: vec![(24, 25), (23, 25), (0, 0)] },
+            )
+            .unwrap();
+        assert_eq!(res.points, vec!["445566", "112233", "000000"]);
+
+        // The default canvas is untouched
+        let res: GetPointsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetPoints { canvas_id: None, coords: vec![(0, 0)] },
+            )
+            .unwrap();
+        assert_eq!(res.points, vec!["aabbcc"]);
+
+        // Regions past the edge of the canvas are rejected
+        app.wrap()
+            .query_wasm_smart::<GetRegionResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::GetRegion { canvas_id, x0: 38, y0: 0, width: 3, height: 1 },
+            )
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_changes_since() {
+        use crate::changes::CHANGE_LOG_CAPACITY;
+        use crate::msg::GetChangesSinceResponse;
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let canvas_id = Some(create_free_canvas(&mut app, &cw_template_contract, 2, 1, None));
+        let user = MockApi::default().addr_make(USER);
+        let set = |app: &mut App, x: u16, z: &str| {
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id, layer: None, x, y: 0, z: z.to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[],
+            )
+            .unwrap();
+        };
+        let changes_since = |app: &App, canvas_id: Option<u32>, height: u64, start_after: Option<u64>, limit: Option<u32>| {
+            app.wrap()
+                .query_wasm_smart::<GetChangesSinceResponse>(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetChangesSince { canvas_id, height, start_after, limit },
+                )
+                .unwrap()
+        };
+        set(&mut app, 0, "111111");
+        let synced_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        set(&mut app, 1, "222222");
+        set(&mut app, 0, "333333");
+
+        let res = changes_since(&app, canvas_id, synced_height, None, Some(1));
+        assert!(!res.truncated);
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!(res.changes[0].z, "222222");
+        assert_eq!(res.changes[0].painter, user);
+        assert_eq!(res.changes[0].height, synced_height + 1);
+        let res = changes_since(&app, canvas_id, synced_height, Some(res.changes[0].seq), None);
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!((res.changes[0].x, res.changes[0].z.as_str()), (0, "333333"));
+
+        // Each canvas keeps its own log
+        let res = changes_since(&app, None, 0, None, None);
+        assert!(res.changes.is_empty());
+        assert!(!res.truncated);
+
+        // Filling the ring buffer drops the oldest changes, so a client synced before them must refetch
+        app.update_block(cw_multi_test::next_block);
+        for i in 0..CHANGE_LOG_CAPACITY {
+            set(&mut app, 1, if i % 2 == 0 { "444444" } else { "555555" });
+        }
+        let res = changes_since(&app, canvas_id, synced_height, None, Some(1));
+        assert!(res.truncated);
+        assert_eq!(res.changes[0].seq, 3);
+        assert_eq!(res.changes[0].z, "444444");
+
+        // A client synced inside the kept window is not told to refetch
+        let filled_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        set(&mut app, 0, "666666");
+        let res = changes_since(&app, canvas_id, filled_height, None, None);
+        assert!(!res.truncated);
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!(res.changes[0].seq, CHANGE_LOG_CAPACITY + 3);
+        assert_eq!(res.changes[0].z, "666666");
+    }
+
+    #[test]
+    fn test_grid_at_height() {
+        use crate::msg::GetPointAtResponse;
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let canvas_id = Some(create_free_canvas(&mut app, &cw_template_contract, 2, 2, None));
+        let created_height = app.block_info().height;
+        let user = MockApi::default().addr_make(USER);
+        let set = |app: &mut App, sender: &Addr, z: &str| {
+            app.execute_contract(
+                sender.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set { canvas_id, layer: None, x: 1, y: 0, z: z.to_string(), blend: None, proof: None, on_behalf_of: None },
+                &[],
+            )
+            .unwrap();
+        };
+        let point_at = |app: &App, height: u64| -> GetPointAtResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetPointAt { canvas_id, x: 1, y: 0, height },
+                )
+                .unwrap()
+        };
+        app.update_block(cw_multi_test::next_block);
+        set(&mut app, &user, "aabbcc");
+        let painted_height = app.block_info().height;
+        app.update_block(cw_multi_test::next_block);
+        set(&mut app, &Addr::unchecked(ADMIN), "ddeeff");
+
+        let res: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetGridAt { canvas_id, height: created_height, start_after: None, limit: None },
+            )
+            .unwrap();
+        assert_eq!(res.z_values, "000000".repeat(4));
+
+        let res = point_at(&app, created_height);
+        assert_eq!(res.point, "000000");
+        assert_eq!(res.painter, None);
+        let res = point_at(&app, painted_height);
+        assert_eq!(res.point, "aabbcc");
+        assert_eq!(res.painter, Some(user));
+        let res = point_at(&app, painted_height + 1);
+        assert_eq!(res.point, "ddeeff");
+        assert_eq!(res.painter, Some(Addr::unchecked(ADMIN)));
+
+        let res: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetGridAt { canvas_id, height: painted_height, start_after: None, limit: None },
+            )
+            .unwrap();
+        assert_eq!(&res.z_values[6..12], "aabbcc");
+
+        // The default canvas keeps its own history
+        let res: GetGridResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetGridAt { canvas_id: None, height: painted_height, start_after: None, limit: None },
+            )
+            .unwrap();
+        assert_eq!(res.z_values, "aabbcc".repeat(4));
+    }
+
+    #[test]
+    fn test_render() {
+        use crate::msg::{ImageFormat, Region, RenderImageResponse, RenderSvgResponse};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        let canvas_id = Some(create_free_canvas(&mut app, &cw_template_contract, 3, 2, None));
+        app.execute_contract(
+            MockApi::default().addr_make(USER),
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set { canvas_id, layer: None, x: 2, y: 0, z: "112233".to_string(), blend: None, proof: None, on_behalf_of: None },
+            &[],
+        )
+        .unwrap();
+
+        let res: RenderSvgResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::RenderSvg { canvas_id, scale: Some(10), region: None },
+            )
+            .unwrap();
+        assert_eq!(
+            res.svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="30" height="20" viewBox="0 0 3 2" shape-rendering="crispEdges">"#,
+                r##"<rect x="0" y="0" width="2" height="1" fill="#000000"/>"##,
+                r##"<rect x="2" y="0" width="1" height="1" fill="#112233"/>"##,
+                r##"<rect x="0" y="1" width="3" height="1" fill="#000000"/>"##,
+                "</svg>",
+            )
+        );
+
+        let render = |format, region| -> RenderImageResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::RenderImage { canvas_id, format, scale: Some(2), region },
+                )
+                .unwrap()
+        };
+        let res = render(ImageFormat::Bmp, None);
+        let bmp = res.image.as_slice();
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32::from_le_bytes(bmp[18..22].try_into().unwrap()), 6);
+        assert_eq!(u32::from_le_bytes(bmp[22..26].try_into().unwrap()), 4);
+        // The last row is stored first; the top right pixel is BGR 112233
+        assert_eq!(&bmp[54 + 20 * 3 + 12..54 + 20 * 3 + 15], &[0x33, 0x22, 0x11]);
+        assert_eq!(res.data_uri, format!("data:image/bmp;base64,{}", res.image.to_base64()));
+
+        let region = Region { x0: 2, y0: 0, width: 1, height: 1 };
+        let res = render(ImageFormat::Png, Some(region));
+        let png = res.image.as_slice();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 2);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 2);
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+        assert!(res.data_uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+    }
+
+    #[test]
+    fn test_chunked_init() {
+        use crate::msg::{GetColorHistogramResponse, ListCanvasesResponse};
+        let (mut app, cw_template_contract) = proper_instantiate(2, 2);
+        // 40x40 grid spans two chunks
+        let canvas_id = Some(create_free_canvas(&mut app, &cw_template_contract, 40, 40, Some(true)));
+        let user = MockApi::default().addr_make(USER);
+        let execute = |app: &mut App, sender: &Addr, msg: ExecuteMsg| {
+            app.execute_contract(sender.clone(), cw_template_contract.addr(), &msg, &[])
+        };
+        let point = |app: &App, x: u16, y: u16| -> String {
+            let res: GetPointResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::GetPoint { canvas_id, x, y })
+                .unwrap();
+            res.point
+        };
+        let histogram = |app: &App| -> Vec<(String, u32)> {
+            let res: GetColorHistogramResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    cw_template_contract.addr(),
+                    &QueryMsg::GetColorHistogram { canvas_id, start_after: None, limit: None },
+                )
+                .unwrap();
+            res.colors.into_iter().map(|c| (c.color, c.count)).collect()
+        };
+        let initializing = |app: &App| -> bool {
+            let res: ListCanvasesResponse = app
+                .wrap()
+                .query_wasm_smart(cw_template_contract.addr(), &QueryMsg::ListCanvases { start_after: None, limit: None })
+                .unwrap();
+            res.canvases.iter().find(|c| Some(c.canvas_id) == canvas_id).unwrap().initializing
+        };
+        let admin = Addr::unchecked(ADMIN);
+        let set = ExecuteMsg::Set { canvas_id, layer: None, x: 0, y: 0, z: "aabbcc".to_string(), blend: None, proof: None, on_behalf_of: None };
+        let err = execute(&mut app, &user, set.clone()).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Canvas is still being initialized");
+        assert!(initializing(&app));
+
+        // The first chunk, then a slice crossing into the second one
+        let init = |offset: u32, data: String| ExecuteMsg::InitChunk { canvas_id, offset, data };
+        let err = execute(&mut app, &user, init(0, "333333".to_string())).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        execute(&mut app, &admin, init(0, "111111".repeat(1024))).unwrap();
+        execute(&mut app, &admin, init(1020, "222222".repeat(8))).unwrap();
+        assert_eq!(point(&app, 19, 25), "111111");
+        assert_eq!(point(&app, 20, 25), "222222");
+        assert_eq!(point(&app, 27, 25), "222222");
+        assert_eq!(point(&app, 28, 25), "000000");
+        // Chunks sent again replace their points in the histogram
+        execute(&mut app, &admin, init(0, "333333".to_string())).unwrap();
+        assert_eq!(
+            histogram(&app),
+            vec![
+                ("000000".to_string(), 1600 - 1028),
+                ("111111".to_string(), 1019),
+                ("222222".to_string(), 8),
+                ("333333".to_string(), 1),
+            ]
+        );
+
+        let err = execute(&mut app, &user, ExecuteMsg::FinalizeInit { canvas_id }).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        execute(&mut app, &admin, ExecuteMsg::FinalizeInit { canvas_id }).unwrap();
+        assert!(!initializing(&app));
+        let err = execute(&mut app, &admin, init(0, "333333".to_string())).unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Canvas is already initialized");
+        execute(&mut app, &user, set).unwrap();
+        assert_eq!(point(&app, 0, 0), "aabbcc");
+    }
 }
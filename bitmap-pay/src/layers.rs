@@ -0,0 +1,179 @@
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage};
+
+use crate::canvas;
+use crate::error::ContractError;
+use crate::grid;
+use crate::msg::{
+    CanvasConfig, GetCompositeResponse, GetLayersResponse, LayerConfig, LayerInfo, Region,
+};
+use crate::state::{Layer, LAYERS, LAYER_PARENT};
+
+/// Name of the canvas itself, the bottom layer of the composite
+pub const BASE_LAYER: &str = "base";
+pub const MAX_LAYERS: usize = 8;
+pub const MAX_LAYER_NAME_LEN: usize = 32;
+
+fn invalid(reason: &str) -> ContractError {
+    ContractError::InvalidLayer {
+        reason: reason.to_string(),
+    }
+}
+
+/// Rejects the canvas_id of a layer, whose points are only written through the
+/// `layer` of its canvas
+pub fn ensure_not_layer(storage: &dyn Storage, canvas_id: u32) -> Result<(), ContractError> {
+    if LAYER_PARENT.has(storage, canvas_id) {
+        return Err(invalid("set a layer through the layer argument of its canvas"));
+    }
+    Ok(())
+}
+
+/// Resolves the canvas a Set paints: the canvas itself without a layer or for
+/// BASE_LAYER, otherwise the canvas of the named layer
+pub fn resolve(
+    storage: &dyn Storage,
+    canvas_id: u32,
+    layer: Option<&str>,
+) -> Result<u32, ContractError> {
+    ensure_not_layer(storage, canvas_id)?;
+    match layer {
+        None | Some(BASE_LAYER) => Ok(canvas_id),
+        Some(name) => LAYERS
+            .may_load(storage, canvas_id)?
+            .unwrap_or_default()
+            .into_iter()
+            .find(|layer| layer.name == name)
+            .map(|layer| layer.canvas_id)
+            .ok_or_else(|| StdError::generic_err("Layer not found").into()),
+    }
+}
+
+/// Creates a canvas and stacks it on top of the canvas's layers (canvas admin only).
+/// The layer takes its size, fee denom and recipient from the canvas, and its own
+/// z_values, access policy and pricing from `config`. Without z_values every point
+/// starts as the `transparent` marker.
+pub fn add_layer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    canvas_id: u32,
+    name: String,
+    transparent: String,
+    config: LayerConfig,
+) -> Result<Response, ContractError> {
+    let state = canvas::load(deps.storage, canvas_id)?;
+    if state.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if LAYER_PARENT.has(deps.storage, canvas_id) {
+        return Err(invalid("a layer cannot have layers"));
+    }
+    if name.is_empty() || name.len() > MAX_LAYER_NAME_LEN || name == BASE_LAYER {
+        return Err(invalid("name must be 1 to 32 characters and not \"base\""));
+    }
+    let transparent = transparent.to_ascii_lowercase();
+    if grid::parse_color(&transparent).is_none() {
+        return Err(ContractError::InvalidZValue {});
+    }
+    let mut layers = LAYERS.may_load(deps.storage, canvas_id)?.unwrap_or_default();
+    if layers.len() >= MAX_LAYERS {
+        return Err(invalid("too many layers"));
+    }
+    if layers.iter().any(|layer| layer.name == name) {
+        return Err(invalid("name is already taken"));
+    }
+
+    let grid_len = grid::grid_len(state.x_size, state.y_size);
+    let config = CanvasConfig {
+        x_size: state.x_size,
+        y_size: state.y_size,
+        z_values: Some(
+            config
+                .z_values
+                .unwrap_or_else(|| transparent.repeat(grid_len as usize)),
+        ),
+        recipient: state.recipient,
+        supply_base_fee: config.supply_base_fee,
+        supply_fee_factor: config.supply_fee_factor,
+        update_base_fee: config.update_base_fee,
+        update_fee_factor: config.update_fee_factor,
+        fee_factor_scale: config.fee_factor_scale,
+        fee_denom: state.fee_denom,
+        max_update_multiplier: config.max_update_multiplier,
+        admin: None,
+        access_policy: config.access_policy,
+        chunked_init: None,
+    };
+    let layer_id = canvas::create(deps.branch(), &env, &info.sender, config)?;
+    layers.push(Layer {
+        name: name.clone(),
+        canvas_id: layer_id,
+        transparent,
+    });
+    LAYERS.save(deps.storage, canvas_id, &layers)?;
+    LAYER_PARENT.save(deps.storage, layer_id, &canvas_id)?;
+    Ok(Response::new()
+        .add_attribute("action", "add_layer")
+        .add_attribute("canvas_id", canvas_id.to_string())
+        .add_attribute("layer", name)
+        .add_attribute("layer_canvas_id", layer_id.to_string()))
+}
+
+pub fn get_layers(deps: Deps, canvas_id: u32) -> StdResult<GetLayersResponse> {
+    canvas::load(deps.storage, canvas_id)?;
+    let layers = LAYERS
+        .may_load(deps.storage, canvas_id)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|layer| LayerInfo {
+            name: layer.name,
+            canvas_id: layer.canvas_id,
+            transparent: layer.transparent,
+        })
+        .collect();
+    Ok(GetLayersResponse { layers })
+}
+
+/// Flattens the layers of a region, the whole grid by default. Each point takes
+/// the topmost layer whose value is not that layer's transparency marker, or the
+/// canvas's own value if every layer is transparent there.
+pub fn get_composite(
+    deps: Deps,
+    canvas_id: u32,
+    region: Option<Region>,
+) -> StdResult<GetCompositeResponse> {
+    let state = canvas::load(deps.storage, canvas_id)?;
+    let region = region.unwrap_or(Region {
+        x0: 0,
+        y0: 0,
+        width: state.x_size,
+        height: state.y_size,
+    });
+    let mut z_values =
+        grid::load_region(deps.storage, canvas_id, state.x_size, state.y_size, &region)?;
+    for layer in LAYERS.may_load(deps.storage, canvas_id)?.unwrap_or_default() {
+        let layer_z = grid::load_region(
+            deps.storage,
+            layer.canvas_id,
+            state.x_size,
+            state.y_size,
+            &region,
+        )?;
+        let mut composite = String::with_capacity(z_values.len());
+        for (below, above) in z_values
+            .as_bytes()
+            .chunks(grid::Z_LEN)
+            .zip(layer_z.as_bytes().chunks(grid::Z_LEN))
+        {
+            let point = if above.eq_ignore_ascii_case(layer.transparent.as_bytes()) {
+                below
+            } else {
+                above
+            };
+            // Both come from hex strings
+            composite.push_str(std::str::from_utf8(point).unwrap_or_default());
+        }
+        z_values = composite;
+    }
+    Ok(GetCompositeResponse { region, z_values })
+}
@@ -1,8 +1,19 @@
+pub mod access;
+pub mod analytics;
+pub mod blend;
+pub mod canvas;
+pub mod changes;
 pub mod contract;
 mod error;
+pub mod grid;
+pub mod heatmap;
 pub mod helpers;
+pub mod init;
 pub mod integration_tests;
+pub mod layers;
 pub mod msg;
+pub mod render;
 pub mod state;
+pub mod treasury;
 
 pub use crate::error::ContractError;
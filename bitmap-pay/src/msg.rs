@@ -1,10 +1,11 @@
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Coin, Uint128};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub x_size: u8,
-    pub y_size: u8,
+    pub x_size: u16,
+    pub y_size: u16,
     pub z_values: Option<String>,
     pub recipient: String,
     pub supply_base_fee: u128,
@@ -13,11 +14,186 @@ pub struct InstantiateMsg {
     pub update_fee_factor: u128,
     pub fee_factor_scale: u128,
     pub fee_denom: String,
+    pub max_update_multiplier: Option<u128>,
+    /// Admin of the contract and of canvas 0, defaults to the instantiating address
+    pub admin: Option<String>,
+    /// Defaults to Open
+    pub access_policy: Option<AccessPolicy>,
+    /// Keeps the canvas closed until the creator sends FinalizeInit, so large
+    /// starting images can be uploaded with InitChunk
+    pub chunked_init: Option<bool>,
 }
 
+/// Settings of a canvas created with CreateCanvas, the same as InstantiateMsg
+#[cw_serde]
+pub struct CanvasConfig {
+    pub x_size: u16,
+    pub y_size: u16,
+    pub z_values: Option<String>,
+    pub recipient: String,
+    pub supply_base_fee: u128,
+    pub supply_fee_factor: u128,
+    pub update_base_fee: u128,
+    pub update_fee_factor: u128,
+    pub fee_factor_scale: u128,
+    pub fee_denom: String,
+    pub max_update_multiplier: Option<u128>,
+    /// Canvas admin, defaults to the sender
+    pub admin: Option<String>,
+    /// Defaults to Open
+    pub access_policy: Option<AccessPolicy>,
+    pub chunked_init: Option<bool>,
+}
+
+/// Settings of a layer created with AddLayer. The layer takes its size, fee denom
+/// and recipient from the canvas it is stacked on.
+#[cw_serde]
+pub struct LayerConfig {
+    /// Defaults to the layer's transparency marker everywhere
+    pub z_values: Option<String>,
+    pub supply_base_fee: u128,
+    pub supply_fee_factor: u128,
+    pub update_base_fee: u128,
+    pub update_fee_factor: u128,
+    pub fee_factor_scale: u128,
+    pub max_update_multiplier: Option<u128>,
+    /// Defaults to Open
+    pub access_policy: Option<AccessPolicy>,
+}
+
+impl From<InstantiateMsg> for CanvasConfig {
+    fn from(msg: InstantiateMsg) -> CanvasConfig {
+        CanvasConfig {
+            x_size: msg.x_size,
+            y_size: msg.y_size,
+            z_values: msg.z_values,
+            recipient: msg.recipient,
+            supply_base_fee: msg.supply_base_fee,
+            supply_fee_factor: msg.supply_fee_factor,
+            update_base_fee: msg.update_base_fee,
+            update_fee_factor: msg.update_fee_factor,
+            fee_factor_scale: msg.fee_factor_scale,
+            fee_denom: msg.fee_denom,
+            max_update_multiplier: msg.max_update_multiplier,
+            admin: msg.admin,
+            access_policy: msg.access_policy,
+            chunked_init: msg.chunked_init,
+        }
+    }
+}
+
+/// Messages that take a `canvas_id` apply to canvas 0, the one created at
+/// instantiate, when it is omitted
 #[cw_serde]
 pub enum ExecuteMsg {
-    Set { x: u8, y: u8, z: String },
+    /// Set paints the point (x, y). `proof` is only needed under a MerkleAllowlist policy.
+    /// `z` is RGB, or RGBA with 8 characters, and `blend` (Replace by default) decides
+    /// how it mixes with the point's current color.
+    /// With `layer`, the point of that layer of the canvas is painted instead, under
+    /// the layer's own access policy and pricing.
+    /// `on_behalf_of` makes that address the painter instead of the sender, and is
+    /// only accepted from the trusted router of the canvas.
+    Set {
+        canvas_id: Option<u32>,
+        layer: Option<String>,
+        x: u16,
+        y: u16,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+        on_behalf_of: Option<String>,
+    },
+
+    /// SetAccessPolicy replaces the access policy (canvas admin only)
+    SetAccessPolicy {
+        canvas_id: Option<u32>,
+        policy: AccessPolicy,
+    },
+
+    /// SetTrustedRouter lets `router` Set on behalf of painters, or removes the trusted
+    /// router without one (canvas admin only)
+    SetTrustedRouter {
+        canvas_id: Option<u32>,
+        router: Option<String>,
+    },
+
+    /// UpdateAllowlist adds and removes addresses under an Allowlist policy (canvas admin only)
+    UpdateAllowlist {
+        canvas_id: Option<u32>,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+
+    /// InitChunk writes consecutive z_values starting at grid index `offset`
+    /// (creator only, before FinalizeInit)
+    InitChunk {
+        canvas_id: Option<u32>,
+        offset: u32,
+        data: String,
+    },
+
+    /// FinalizeInit opens the canvas for Set (creator only)
+    FinalizeInit { canvas_id: Option<u32> },
+
+    /// CreateCanvas adds a canvas with its own size, pricing and recipient (admin only).
+    /// Its canvas_id is returned in the `canvas_id` attribute.
+    CreateCanvas { config: Box<CanvasConfig> },
+
+    /// AddLayer creates a canvas with the size, fee denom and recipient of the canvas
+    /// and the pricing and access policy of `config`, and stacks it on top of the
+    /// canvas's layers (canvas admin only). Points of the layer equal to
+    /// `transparent`, which the caller picks, show the layers below; painting a
+    /// point with it clears the point again.
+    /// The layer's canvas_id is returned in the `layer_canvas_id` attribute.
+    AddLayer {
+        canvas_id: Option<u32>,
+        name: String,
+        transparent: String,
+        config: Box<LayerConfig>,
+    },
+
+    /// Withdraw sends funds held by the contract to `to` (admin only)
+    Withdraw {
+        denom: String,
+        amount: Uint128,
+        to: String,
+    },
+}
+
+#[cw_serde]
+pub struct Region {
+    pub x0: u16,
+    pub y0: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// How a Set mixes with the current color of the point
+#[cw_serde]
+#[derive(Default)]
+pub enum BlendMode {
+    /// Overwrites the point, ignoring alpha
+    #[default]
+    Replace,
+    /// Paints over the point, weighted by the alpha of z
+    AlphaOver,
+    /// Multiplies the point by z, weighted by the alpha of z
+    Multiply,
+}
+
+/// Who can set points
+#[cw_serde]
+pub enum AccessPolicy {
+    /// Anyone
+    Open {},
+    /// Only the listed addresses, managed by the admin
+    Allowlist(Vec<Addr>),
+    /// Addresses holding at least `min` of a native denom
+    HoldsNativeBalance { denom: String, min: Uint128 },
+    /// Addresses owning at least one token of a cw721 collection
+    HoldsCw721 { collection: String },
+    /// Addresses whose sha256 hash is a leaf of the merkle tree with this hex-encoded root
+    MerkleAllowlist { root: String },
 }
 
 #[cw_serde]
@@ -25,38 +201,215 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     /// GetPoint returns the point (z) at (x, y)
     #[returns(GetPointResponse)]
-    GetPoint { x: u8, y: u8 },
+    GetPoint {
+        canvas_id: Option<u32>,
+        x: u16,
+        y: u16,
+    },
+
+    /// GetGrid returns the grid as a string, one page of chunks at a time.
+    /// Grids up to 255x255 fit in a single page.
+    #[returns(GetGridResponse)]
+    GetGrid {
+        canvas_id: Option<u32>,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
 
-    /// GetGrid returns the entire grid as a string
+    /// GetGridAt returns a page of the grid as it was at the end of block `height`
     #[returns(GetGridResponse)]
-    GetGrid {},
+    GetGridAt {
+        canvas_id: Option<u32>,
+        height: u64,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+
+    /// GetPointAt returns the point (z) at (x, y) and its painter at the end of block `height`
+    #[returns(GetPointAtResponse)]
+    GetPointAt {
+        canvas_id: Option<u32>,
+        x: u16,
+        y: u16,
+        height: u64,
+    },
+
+    /// GetRegion returns a rectangle of the grid, row by row
+    #[returns(GetRegionResponse)]
+    GetRegion {
+        canvas_id: Option<u32>,
+        x0: u16,
+        y0: u16,
+        width: u16,
+        height: u16,
+    },
+
+    /// GetRow returns a full row of the grid
+    #[returns(GetRowResponse)]
+    GetRow { canvas_id: Option<u32>, y: u16 },
+
+    /// GetPoints returns the points (z) at each (x, y), in the same order
+    #[returns(GetPointsResponse)]
+    GetPoints {
+        canvas_id: Option<u32>,
+        coords: Vec<(u16, u16)>,
+    },
+
+    /// RenderSvg returns the grid, or a region of it, as an SVG document
+    /// where each point is `scale` pixels wide
+    #[returns(RenderSvgResponse)]
+    RenderSvg {
+        canvas_id: Option<u32>,
+        scale: Option<u32>,
+        region: Option<Region>,
+    },
+
+    /// RenderImage returns the grid, or a region of it, as a BMP or PNG image
+    /// along with a `data:` URI that can be used directly as an image source
+    #[returns(RenderImageResponse)]
+    RenderImage {
+        canvas_id: Option<u32>,
+        format: ImageFormat,
+        scale: Option<u32>,
+        region: Option<Region>,
+    },
+
+    /// GetHeatmap returns the update count of each point of the grid, or a region
+    /// of it, as a matrix. With recency_bucket, each point holds how many buckets
+    /// of recency_bucket blocks ago it was last painted instead, 0 if never.
+    #[returns(GetHeatmapResponse)]
+    GetHeatmap {
+        canvas_id: Option<u32>,
+        region: Option<Region>,
+        recency_bucket: Option<u64>,
+    },
+
+    /// GetAccessPolicy returns who can set points
+    #[returns(AccessPolicy)]
+    GetAccessPolicy { canvas_id: Option<u32> },
+
+    /// GetTrustedRouter returns the router allowed to Set on behalf of painters
+    #[returns(GetTrustedRouterResponse)]
+    GetTrustedRouter { canvas_id: Option<u32> },
+
+    /// GetChangesSince returns the points painted after a block height, oldest first
+    #[returns(GetChangesSinceResponse)]
+    GetChangesSince {
+        canvas_id: Option<u32>,
+        height: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 
     // GetCost returns the cost to set a point (x, y)
     #[returns(GetCostResponse)]
-    GetCost { x: u8, y: u8 },
+    GetCost {
+        canvas_id: Option<u32>,
+        x: u16,
+        y: u16,
+    },
+
+    // GetPriceLadder previews the next `steps` prices of the supply curve and of
+    // the update curve of point (x, y), starting with the current ones
+    #[returns(GetPriceLadderResponse)]
+    GetPriceLadder {
+        canvas_id: Option<u32>,
+        x: u16,
+        y: u16,
+        steps: u32,
+    },
+
+    /// GetTreasury returns revenue totals of all canvases and reconciles them
+    /// against the contract's balance
+    #[returns(GetTreasuryResponse)]
+    GetTreasury {},
+
+    /// GetStats returns running totals of the canvas activity
+    #[returns(GetStatsResponse)]
+    GetStats { canvas_id: Option<u32> },
+
+    /// GetTopPainters returns the painters of a canvas with the most pixels held
+    /// or the most spent
+    #[returns(GetTopPaintersResponse)]
+    GetTopPainters {
+        canvas_id: Option<u32>,
+        by: PainterRanking,
+        limit: Option<u32>,
+    },
+
+    /// GetColorHistogram returns the number of points of each color, in color order
+    #[returns(GetColorHistogramResponse)]
+    GetColorHistogram {
+        canvas_id: Option<u32>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// GetLayers returns the layers of the canvas, bottom to top
+    #[returns(GetLayersResponse)]
+    GetLayers { canvas_id: Option<u32> },
+
+    /// GetComposite returns the grid, or a region of it, with the layers
+    /// flattened on top of the canvas, row by row
+    #[returns(GetCompositeResponse)]
+    GetComposite {
+        canvas_id: Option<u32>,
+        region: Option<Region>,
+    },
+
+    /// ListCanvases returns the canvases in canvas_id order, without layers
+    #[returns(ListCanvasesResponse)]
+    ListCanvases {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
 
     // GetParams returns the curve parameters (base, factor)
     #[returns(GetParamsResponse)]
-    GetParams {},
+    GetParams { canvas_id: Option<u32> },
 }
 
 #[cw_serde]
 pub struct GetCostResponse {
+    /// supply_cost + update_cost
     pub cost: u128,
+    pub supply_cost: u128,
+    pub update_cost: u128,
+    pub denom: String,
+    pub num_set: u32,
+    pub update_count: u32,
+}
+
+#[cw_serde]
+pub struct GetPriceLadderResponse {
+    pub denom: String,
+    /// Supply curve price after 0, 1, 2... more points are set
+    pub supply_prices: Vec<u128>,
+    /// Update curve price of the point after 0, 1, 2... more updates
+    pub update_prices: Vec<u128>,
+}
+
+#[cw_serde]
+pub struct GetTrustedRouterResponse {
+    pub router: Option<Addr>,
 }
 
 #[cw_serde]
 pub struct GetPointResponse {
     pub point: String,
     pub is_set: bool,
-    pub update_count: u8,
+    pub update_count: u32,
 }
 
 #[cw_serde]
 pub struct GetGridResponse {
-    pub x_size: u8,
-    pub y_size: u8,
+    pub x_size: u16,
+    pub y_size: u16,
+    /// Grid index of the first point in z_values
+    pub offset: u32,
     pub z_values: String,
+    /// Chunk to pass as start_after to fetch the next page, None on the last page
+    pub next_start_after: Option<u32>,
 }
 
 #[cw_serde]
@@ -67,4 +420,194 @@ pub struct GetParamsResponse {
     pub update_fee_factor: u128,
     pub fee_factor_scale: u128,
     pub fee_denom: String,
-}
\ No newline at end of file
+    pub max_update_multiplier: Option<u128>,
+    /// Number of points that have been set at least once
+    pub num_set: u32,
+}
+
+#[cw_serde]
+pub struct GetRegionResponse {
+    pub x0: u16,
+    pub y0: u16,
+    pub width: u16,
+    pub height: u16,
+    pub z_values: String,
+}
+
+#[cw_serde]
+pub struct GetRowResponse {
+    pub y: u16,
+    pub z_values: String,
+}
+
+#[cw_serde]
+pub struct GetPointsResponse {
+    pub points: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ChangeInfo {
+    pub seq: u64,
+    pub height: u64,
+    pub x: u16,
+    pub y: u16,
+    pub z: String,
+    pub painter: Addr,
+}
+
+#[cw_serde]
+pub struct GetChangesSinceResponse {
+    pub changes: Vec<ChangeInfo>,
+    /// True if older changes after the height were dropped from the log and the grid must be refetched
+    pub truncated: bool,
+}
+
+#[cw_serde]
+pub struct GetPointAtResponse {
+    pub point: String,
+    /// None if the point had not been painted yet
+    pub painter: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct RenderSvgResponse {
+    pub svg: String,
+}
+
+#[cw_serde]
+pub enum ImageFormat {
+    Bmp,
+    Png,
+}
+
+#[cw_serde]
+pub struct RenderImageResponse {
+    pub image: Binary,
+    pub data_uri: String,
+}
+
+#[cw_serde]
+pub struct LayerInfo {
+    pub name: String,
+    pub canvas_id: u32,
+    pub transparent: String,
+}
+
+#[cw_serde]
+pub struct GetLayersResponse {
+    /// Bottom to top, above the canvas itself
+    pub layers: Vec<LayerInfo>,
+}
+
+#[cw_serde]
+pub struct GetCompositeResponse {
+    pub region: Region,
+    pub z_values: String,
+}
+
+#[cw_serde]
+pub struct GetHeatmapResponse {
+    pub region: Region,
+    /// Size of each value in data: 1, 2, 4 or 8 bytes, the smallest that fits max_value
+    pub bytes_per_cell: u8,
+    pub max_value: u64,
+    /// Big-endian values, row by row
+    pub data: Binary,
+}
+
+#[cw_serde]
+pub struct DenomTreasury {
+    pub denom: String,
+    /// Costs of Set forwarded to the recipient
+    pub revenue: Uint128,
+    /// Excess payments and other denoms sent with Set, kept by the contract
+    pub retained: Uint128,
+    pub withdrawn: Uint128,
+    /// retained - withdrawn, what the contract should hold
+    pub expected_balance: Uint128,
+    /// What the contract actually holds
+    pub balance: Uint128,
+    /// Funds held beyond the expected balance, such as direct bank sends
+    pub unrecorded: Uint128,
+    /// Expected funds missing from the balance
+    pub shortfall: Uint128,
+}
+
+#[cw_serde]
+pub struct GetTreasuryResponse {
+    pub paints: u64,
+    pub unique_painters: u64,
+    /// The fee denoms of all canvases and every denom received through Set, in denom order
+    pub denoms: Vec<DenomTreasury>,
+}
+
+#[cw_serde]
+pub struct MostPainted {
+    pub x: u16,
+    pub y: u16,
+    pub update_count: u32,
+}
+
+#[cw_serde]
+pub struct GetStatsResponse {
+    pub paints: u64,
+    /// Number of points that have been set at least once
+    pub pixels_set: u32,
+    pub unique_painters: u64,
+    /// Costs of Set paid in the canvas fee denom
+    pub total_revenue: Coin,
+    /// None until a point is set
+    pub most_painted: Option<MostPainted>,
+}
+
+#[cw_serde]
+pub enum PainterRanking {
+    PixelsHeld,
+    AmountSpent,
+}
+
+#[cw_serde]
+pub struct PainterInfo {
+    pub painter: Addr,
+    /// Points whose last painter is this address
+    pub pixels_held: u32,
+    /// Costs paid on the canvas, in its fee denom
+    pub amount_spent: Coin,
+    pub paints: u64,
+}
+
+#[cw_serde]
+pub struct GetTopPaintersResponse {
+    pub painters: Vec<PainterInfo>,
+}
+
+#[cw_serde]
+pub struct ColorCount {
+    /// Lowercase z value
+    pub color: String,
+    pub count: u32,
+}
+
+#[cw_serde]
+pub struct GetColorHistogramResponse {
+    pub colors: Vec<ColorCount>,
+}
+
+#[cw_serde]
+pub struct CanvasInfo {
+    pub canvas_id: u32,
+    pub x_size: u16,
+    pub y_size: u16,
+    pub admin: Addr,
+    pub recipient: String,
+    pub fee_denom: String,
+    /// Number of points that have been set at least once
+    pub num_set: u32,
+    /// True until the creator of a chunked_init canvas sends FinalizeInit
+    pub initializing: bool,
+}
+
+#[cw_serde]
+pub struct ListCanvasesResponse {
+    pub canvases: Vec<CanvasInfo>,
+}
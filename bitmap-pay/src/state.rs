@@ -1,13 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cw_storage_plus::Item;
+use crate::msg::AccessPolicy;
+
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct State {
-	pub x_size: u8,
-	pub y_size: u8,
-	pub z_values: String,
+	pub x_size: u16,
+	pub y_size: u16,
+	pub admin: Addr, // canvas admin, who manages its access policy
 	pub recipient: String,
 	pub supply_base_fee: u128,
 	pub supply_fee_factor: u128,
@@ -15,9 +18,122 @@ pub struct State {
 	pub update_fee_factor: u128,
 	pub fee_factor_scale: u128,
 	pub fee_denom: String,
-	pub set_points: Vec<u8>, // bitfield: each bit represents a set point
-	pub update_counts: Vec<u8>, // each byte is the update count for a point
+	pub max_update_multiplier: Option<u128>, // caps the update curve at update_base_fee * max
+	pub num_set: u32, // number of points that have been set at least once
+}
+
+// Canvases keyed by canvas_id. Canvas 0 is created at instantiate.
+pub const CANVASES: Map<u32, State> = Map::new("canvases");
+// canvas_id of the next CreateCanvas
+pub const NEXT_CANVAS_ID: Item<u32> = Item::new("next_canvas_id");
+// Contract admin, who creates canvases and withdraws funds
+pub const ADMIN: Item<Addr> = Item::new("admin");
+
+// z_values split into chunks of grid::CHUNK_SIZE points, keyed by canvas_id and chunk index.
+// Snapshotted every block so the grid can be queried at past heights.
+pub const Z_CHUNKS: SnapshotMap<(u32, u32), String> = SnapshotMap::new(
+	"z_chunks",
+	"z_chunks__checkpoints",
+	"z_chunks__changelog",
+	Strategy::EveryBlock,
+);
+
+// Last painter of each point, keyed by canvas_id and grid index
+pub const PAINTERS: SnapshotMap<(u32, u32), Addr> = SnapshotMap::new(
+	"painters",
+	"painters__checkpoints",
+	"painters__changelog",
+	Strategy::EveryBlock,
+);
+
+// Bitfield per canvas_id and chunk: each bit represents a set point
+pub const SET_POINTS: Map<(u32, u32), Vec<u8>> = Map::new("set_points");
+
+// Update count per point, keyed by canvas_id and grid index. Points that were never set have no entry.
+pub const UPDATE_COUNTS: Map<(u32, u32), u32> = Map::new("update_counts");
+// Block height of the last Set of each point, keyed by canvas_id and grid index
+pub const LAST_PAINTED: Map<(u32, u32), u64> = Map::new("last_painted");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Change {
+	pub height: u64,
+	pub x: u16,
+	pub y: u16,
+	pub z: String,
+	pub painter: Addr,
 }
 
-pub const STATE: Item<State> = Item::new("state");
+// Ring buffer of the most recent changes of each canvas, keyed by canvas_id and sequence number
+pub const CHANGES: Map<(u32, u64), Change> = Map::new("changes");
+// Sequence number of the next change, per canvas_id
+pub const NEXT_CHANGE: Map<u32, u64> = Map::new("next_change");
+
+// Who can set points on each canvas, Open if missing
+pub const ACCESS_POLICY: Map<u32, AccessPolicy> = Map::new("access_policy");
+
+// Router allowed to Set on behalf of painters, per canvas_id
+pub const TRUSTED_ROUTER: Map<u32, Addr> = Map::new("trusted_router");
+
+// Creator allowed to send InitChunk, per canvas_id; present until FinalizeInit opens the canvas
+pub const INIT_CREATOR: Map<u32, Addr> = Map::new("init_creator");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Layer {
+	pub name: String,
+	pub canvas_id: u32, // canvas holding the layer's points, pricing and access policy
+	pub transparent: String, // lowercase z value shown as the layers below
+}
+
+// Layers of each canvas_id, bottom to top above the canvas itself
+pub const LAYERS: Map<u32, Vec<Layer>> = Map::new("layers");
+// Canvas each layer canvas belongs to, keyed by the layer's canvas_id
+pub const LAYER_PARENT: Map<u32, u32> = Map::new("layer_parent");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct TreasuryTotals {
+	pub paints: u64,
+	pub unique_painters: u64,
+}
+
+pub const TREASURY: Item<TreasuryTotals> = Item::new("treasury");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct DenomTotals {
+	pub revenue: Uint128, // costs forwarded to the recipient
+	pub retained: Uint128, // excess payments and other denoms kept by the contract
+	pub withdrawn: Uint128,
+}
+
+// Running totals per denom, keyed by denom
+pub const DENOM_TOTALS: Map<&str, DenomTotals> = Map::new("denom_totals");
+// Every address that has set a point
+pub const KNOWN_PAINTERS: Map<&Addr, Empty> = Map::new("known_painters");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct PainterStats {
+	pub pixels_held: u32, // points whose last painter is this address
+	pub amount_spent: u128, // costs paid in the canvas fee_denom
+	pub paints: u64,
+}
+
+// Stats per canvas_id and painter
+pub const PAINTER_STATS: Map<(u32, &Addr), PainterStats> = Map::new("canvas_painter_stats");
+// Painters of each canvas_id ranked by pixels_held and by amount_spent, kept in sync with PAINTER_STATS
+pub const HELD_RANK: Map<(u32, u32, &Addr), Empty> = Map::new("canvas_held_rank");
+pub const SPENT_RANK: Map<(u32, u128, &Addr), Empty> = Map::new("canvas_spent_rank");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct CanvasStats {
+	pub paints: u64,
+	pub unique_painters: u64,
+	pub revenue: u128, // costs paid in the canvas fee_denom
+}
 
+// Stats per canvas_id
+pub const CANVAS_STATS: Map<u32, CanvasStats> = Map::new("canvas_stats");
+// Every address that has set a point, per canvas_id
+pub const CANVAS_PAINTERS: Map<(u32, &Addr), Empty> = Map::new("canvas_painters");
+// Number of points of each color, keyed by canvas_id and lowercase z. Colors with no points have no entry.
+pub const COLOR_COUNTS: Map<(u32, &str), u32> = Map::new("color_counts");
+// Grid index and update count of the most updated point, per canvas_id
+pub const MOST_PAINTED: Map<u32, (u32, u32)> = Map::new("most_painted");
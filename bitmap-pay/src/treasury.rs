@@ -0,0 +1,114 @@
+use cosmwasm_std::{
+    Addr, BankMsg, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult,
+    Storage, Uint128,
+};
+
+use crate::error::ContractError;
+use crate::msg::{DenomTreasury, GetTreasuryResponse};
+use crate::state::{DenomTotals, ADMIN, DENOM_TOTALS, KNOWN_PAINTERS, TREASURY};
+
+/// Records a paid Set: the cost forwarded in fee_denom, and whatever else was
+/// sent along with it, which stays in the contract
+pub fn record_paint(
+    storage: &mut dyn Storage,
+    painter: &Addr,
+    funds: &[Coin],
+    fee_denom: &str,
+    cost: u128,
+) -> StdResult<()> {
+    let mut totals = TREASURY.may_load(storage)?.unwrap_or_default();
+    totals.paints += 1;
+    if !KNOWN_PAINTERS.has(storage, painter) {
+        KNOWN_PAINTERS.save(storage, painter, &Empty {})?;
+        totals.unique_painters += 1;
+    }
+    TREASURY.save(storage, &totals)?;
+
+    let cost = Uint128::new(cost);
+    let mut fee_totals = DENOM_TOTALS.may_load(storage, fee_denom)?.unwrap_or_default();
+    fee_totals.revenue += cost;
+    DENOM_TOTALS.save(storage, fee_denom, &fee_totals)?;
+    for coin in funds {
+        let retained = if coin.denom == fee_denom {
+            coin.amount.saturating_sub(cost)
+        } else {
+            coin.amount
+        };
+        if !retained.is_zero() {
+            DENOM_TOTALS.update(storage, &coin.denom, |totals| -> StdResult<_> {
+                let mut totals = totals.unwrap_or_default();
+                totals.retained += retained;
+                Ok(totals)
+            })?;
+        }
+    }
+    Ok(())
+}
+
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    amount: Uint128,
+    to: String,
+) -> Result<Response, ContractError> {
+    if ADMIN.load(deps.storage)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let to = deps.api.addr_validate(&to)?;
+    let balance = deps.querier.query_balance(&env.contract.address, &denom)?;
+    if amount.is_zero() || amount > balance.amount {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    DENOM_TOTALS.update(deps.storage, &denom, |totals| -> StdResult<_> {
+        let mut totals = totals.unwrap_or_default();
+        totals.withdrawn += amount;
+        Ok(totals)
+    })?;
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![Coin::new(amount, denom.clone())],
+        })
+        .add_attribute("action", "withdraw")
+        .add_attribute("denom", denom)
+        .add_attribute("amount", amount)
+        .add_attribute("to", to))
+}
+
+pub fn get_treasury(deps: Deps, env: Env) -> StdResult<GetTreasuryResponse> {
+    let totals = TREASURY.may_load(deps.storage)?.unwrap_or_default();
+    // Fee denoms are listed when their canvas is created
+    let denoms = DENOM_TOTALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (
+                denom,
+                DenomTotals {
+                    revenue,
+                    retained,
+                    withdrawn,
+                },
+            ) = item?;
+            let balance = deps.querier.query_balance(&env.contract.address, &denom)?.amount;
+            // Withdrawals can also move unrecorded funds, so they may exceed retained
+            let expected_balance = retained.saturating_sub(withdrawn);
+            Ok(DenomTreasury {
+                denom,
+                revenue,
+                retained,
+                withdrawn,
+                expected_balance,
+                balance,
+                unrecorded: balance.saturating_sub(expected_balance),
+                shortfall: expected_balance.saturating_sub(balance),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetTreasuryResponse {
+        paints: totals.paints,
+        unique_painters: totals.unique_painters,
+        denoms,
+    })
+}
@@ -0,0 +1,389 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::state::{Config, CONFIG};
+use crate::tiles;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:bitmap-router";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    if msg.tile_width == 0 || msg.tile_height == 0 || msg.tiles_x == 0 || msg.tiles_y == 0 {
+        return Err(ContractError::InvalidWorldSize {});
+    }
+    let admin = match msg.admin {
+        Some(ref admin) => deps.api.addr_validate(admin)?,
+        None => info.sender.clone(),
+    };
+    let config = Config {
+        admin,
+        tile_width: msg.tile_width,
+        tile_height: msg.tile_height,
+        tiles_x: msg.tiles_x,
+        tiles_y: msg.tiles_y,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    let (world_width, world_height) = tiles::world_size(&config);
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", config.admin)
+        .add_attribute("world_width", world_width.to_string())
+        .add_attribute("world_height", world_height.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetTile {
+            tile_x,
+            tile_y,
+            contract,
+            canvas_id,
+        } => execute::set_tile(deps, info, tile_x, tile_y, contract, canvas_id),
+        ExecuteMsg::RemoveTile { tile_x, tile_y } => {
+            execute::remove_tile(deps, info, tile_x, tile_y)
+        }
+        ExecuteMsg::Set {
+            x,
+            y,
+            z,
+            blend,
+            proof,
+        } => execute::set(deps, info, x, y, z, blend, proof),
+        ExecuteMsg::SetBatch { points } => execute::set_batch(deps, info, points),
+    }
+}
+
+pub mod execute {
+    use super::*;
+
+    use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, WasmMsg};
+
+    use bitmap_pay::msg::{
+        BlendMode, ExecuteMsg as TileExecuteMsg, GetGridResponse, QueryMsg as TileQueryMsg,
+    };
+
+    use crate::msg::{PointCost, WorldPoint};
+    use crate::state::{Tile, TILES};
+
+    fn ensure_admin(deps: Deps, info: &MessageInfo) -> Result<Config, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        if info.sender != config.admin {
+            return Err(ContractError::Unauthorized {});
+        }
+        Ok(config)
+    }
+
+    pub fn set_tile(
+        deps: DepsMut,
+        info: MessageInfo,
+        tile_x: u16,
+        tile_y: u16,
+        contract: String,
+        canvas_id: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let config = ensure_admin(deps.as_ref(), &info)?;
+        if tile_x >= config.tiles_x || tile_y >= config.tiles_y {
+            return Err(ContractError::TileOutOfBounds { tile_x, tile_y });
+        }
+        let contract = deps.api.addr_validate(&contract)?;
+        // The first grid page carries the canvas size
+        let grid: GetGridResponse = deps.querier.query_wasm_smart(
+            &contract,
+            &TileQueryMsg::GetGrid {
+                canvas_id,
+                start_after: None,
+                limit: Some(1),
+            },
+        )?;
+        if grid.x_size != config.tile_width || grid.y_size != config.tile_height {
+            return Err(ContractError::TileSizeMismatch {
+                x_size: grid.x_size,
+                y_size: grid.y_size,
+                tile_width: config.tile_width,
+                tile_height: config.tile_height,
+            });
+        }
+        TILES.save(
+            deps.storage,
+            (tile_y, tile_x),
+            &Tile {
+                contract: contract.clone(),
+                canvas_id,
+            },
+        )?;
+        Ok(Response::new()
+            .add_attribute("method", "set_tile")
+            .add_attribute("tile_x", tile_x.to_string())
+            .add_attribute("tile_y", tile_y.to_string())
+            .add_attribute("contract", contract))
+    }
+
+    pub fn remove_tile(
+        deps: DepsMut,
+        info: MessageInfo,
+        tile_x: u16,
+        tile_y: u16,
+    ) -> Result<Response, ContractError> {
+        ensure_admin(deps.as_ref(), &info)?;
+        tiles::load(deps.as_ref(), tile_x, tile_y)?;
+        TILES.remove(deps.storage, (tile_y, tile_x));
+        Ok(Response::new()
+            .add_attribute("method", "remove_tile")
+            .add_attribute("tile_x", tile_x.to_string())
+            .add_attribute("tile_y", tile_y.to_string()))
+    }
+
+    /// Paints a tile point on behalf of `painter`, which needs the tile canvas to
+    /// trust the router
+    #[allow(clippy::too_many_arguments)]
+    fn tile_set(
+        tile: &Tile,
+        painter: &Addr,
+        x: u16,
+        y: u16,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+        funds: Vec<Coin>,
+    ) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: tile.contract.to_string(),
+            msg: to_json_binary(&TileExecuteMsg::Set {
+                canvas_id: tile.canvas_id,
+                layer: None,
+                x,
+                y,
+                z,
+                blend,
+                proof,
+                on_behalf_of: Some(painter.to_string()),
+            })?,
+            funds,
+        })
+    }
+
+    /// Funds the tiles need for `costs`, and what is left of `funds` to refund
+    fn split_funds(
+        funds: &[Coin],
+        costs: &[PointCost],
+    ) -> Result<(Vec<Vec<Coin>>, Vec<Coin>), ContractError> {
+        let mut refund = funds.to_vec();
+        for total in tiles::totals(costs) {
+            if total.amount.is_zero() {
+                continue;
+            }
+            let sent = refund
+                .iter_mut()
+                .find(|coin| coin.denom == total.denom)
+                .ok_or(ContractError::InsufficientFunds {})?;
+            sent.amount = sent
+                .amount
+                .checked_sub(total.amount)
+                .map_err(|_| ContractError::InsufficientFunds {})?;
+        }
+        refund.retain(|coin| !coin.amount.is_zero());
+        let point_funds = costs
+            .iter()
+            .map(|cost| {
+                if cost.cost == 0 {
+                    vec![]
+                } else {
+                    vec![Coin::new(cost.cost, cost.denom.clone())]
+                }
+            })
+            .collect();
+        Ok((point_funds, refund))
+    }
+
+    fn refund_msg(to: &Addr, refund: Vec<Coin>) -> Option<CosmosMsg> {
+        if refund.is_empty() {
+            return None;
+        }
+        Some(
+            BankMsg::Send {
+                to_address: to.to_string(),
+                amount: refund,
+            }
+            .into(),
+        )
+    }
+
+    pub fn set(
+        deps: DepsMut,
+        info: MessageInfo,
+        x: u32,
+        y: u32,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        let point = tiles::locate(&config, x, y)?;
+        let tile = tiles::load(deps.as_ref(), point.tile_x, point.tile_y)?;
+        // The tile is sent exactly its price and the excess is refunded, as in SetBatch
+        let costs = tiles::batch_costs(deps.as_ref(), &[(x, y)])?;
+        let (mut point_funds, refund) = split_funds(&info.funds, &costs)?;
+        let funds = point_funds.remove(0);
+        let msg = tile_set(&tile, &info.sender, point.x, point.y, z, blend, proof, funds)?;
+        Ok(Response::new()
+            .add_message(msg)
+            .add_messages(refund_msg(&info.sender, refund))
+            .add_attribute("method", "set")
+            .add_attribute("x", x.to_string())
+            .add_attribute("y", y.to_string())
+            .add_attribute("tile_x", point.tile_x.to_string())
+            .add_attribute("tile_y", point.tile_y.to_string())
+            .add_attribute("from", info.sender))
+    }
+
+    pub fn set_batch(
+        deps: DepsMut,
+        info: MessageInfo,
+        points: Vec<WorldPoint>,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        let coords: Vec<(u32, u32)> = points.iter().map(|point| (point.x, point.y)).collect();
+        let costs = tiles::batch_costs(deps.as_ref(), &coords)?;
+
+        // Everything above the totals goes back to the sender
+        let (point_funds, refund) = split_funds(&info.funds, &costs)?;
+
+        let mut msgs: Vec<CosmosMsg> = Vec::with_capacity(points.len() + 1);
+        for (point, funds) in points.into_iter().zip(point_funds) {
+            let located = tiles::locate(&config, point.x, point.y)?;
+            let tile = tiles::load(deps.as_ref(), located.tile_x, located.tile_y)?;
+            let msg = tile_set(
+                &tile,
+                &info.sender,
+                located.x,
+                located.y,
+                point.z,
+                point.blend,
+                point.proof,
+                funds,
+            )?;
+            msgs.push(msg.into());
+        }
+        msgs.extend(refund_msg(&info.sender, refund));
+        let num_points = coords.len();
+        Ok(Response::new()
+            .add_messages(msgs)
+            .add_attribute("method", "set_batch")
+            .add_attribute("points", num_points.to_string())
+            .add_attribute("from", info.sender))
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_json_binary(&query::config(deps)?),
+        QueryMsg::GetTile { x, y } => to_json_binary(&query::get_tile(deps, x, y)?),
+        QueryMsg::ListTiles { start_after, limit } => {
+            to_json_binary(&query::list_tiles(deps, start_after, limit)?)
+        }
+        QueryMsg::GetCost { coords } => to_json_binary(&query::get_cost(deps, coords)?),
+        QueryMsg::GetRegion {
+            x0,
+            y0,
+            width,
+            height,
+        } => to_json_binary(&tiles::get_region(deps, x0, y0, width, height)?),
+    }
+}
+
+pub mod query {
+    use super::*;
+
+    use cosmwasm_std::Order;
+    use cw_storage_plus::Bound;
+
+    use crate::msg::{
+        ConfigResponse, GetCostResponse, GetTileResponse, ListTilesResponse, TileInfo,
+    };
+    use crate::state::TILES;
+
+    pub const DEFAULT_TILES_LIMIT: u32 = 30;
+    pub const MAX_TILES_LIMIT: u32 = 100;
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let (world_width, world_height) = tiles::world_size(&config);
+        Ok(ConfigResponse {
+            admin: config.admin,
+            tile_width: config.tile_width,
+            tile_height: config.tile_height,
+            tiles_x: config.tiles_x,
+            tiles_y: config.tiles_y,
+            world_width,
+            world_height,
+        })
+    }
+
+    pub fn get_tile(deps: Deps, x: u32, y: u32) -> StdResult<GetTileResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let point = tiles::locate(&config, x, y)?;
+        let tile = TILES
+            .may_load(deps.storage, (point.tile_y, point.tile_x))?
+            .map(|tile| TileInfo {
+                tile_x: point.tile_x,
+                tile_y: point.tile_y,
+                contract: tile.contract,
+                canvas_id: tile.canvas_id,
+            });
+        Ok(GetTileResponse {
+            tile_x: point.tile_x,
+            tile_y: point.tile_y,
+            x: point.x,
+            y: point.y,
+            tile,
+        })
+    }
+
+    pub fn list_tiles(
+        deps: Deps,
+        start_after: Option<(u16, u16)>,
+        limit: Option<u32>,
+    ) -> StdResult<ListTilesResponse> {
+        let limit = limit.unwrap_or(DEFAULT_TILES_LIMIT).min(MAX_TILES_LIMIT) as usize;
+        let start = start_after.map(|(tile_x, tile_y)| Bound::exclusive((tile_y, tile_x)));
+        let tiles = TILES
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let ((tile_y, tile_x), tile) = item?;
+                Ok(TileInfo {
+                    tile_x,
+                    tile_y,
+                    contract: tile.contract,
+                    canvas_id: tile.canvas_id,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        Ok(ListTilesResponse { tiles })
+    }
+
+    pub fn get_cost(deps: Deps, coords: Vec<(u32, u32)>) -> StdResult<GetCostResponse> {
+        let costs = tiles::batch_costs(deps, &coords)?;
+        let totals = tiles::totals(&costs);
+        Ok(GetCostResponse { costs, totals })
+    }
+}
@@ -0,0 +1,24 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+    #[error("Invalid world size")]
+    InvalidWorldSize {},
+    #[error("Tile ({tile_x}, {tile_y}) is outside the world")]
+    TileOutOfBounds { tile_x: u16, tile_y: u16 },
+    #[error("Tile canvas is {x_size}x{y_size}, expected {tile_width}x{tile_height}")]
+    TileSizeMismatch {
+        x_size: u16,
+        y_size: u16,
+        tile_width: u16,
+        tile_height: u16,
+    },
+    #[error("Insufficient funds")]
+    InsufficientFunds {},
+}
@@ -0,0 +1,528 @@
+#[cfg(test)]
+mod tests {
+    use crate::helpers::CwTemplateContract;
+    use crate::msg::{
+        ExecuteMsg, GetCostResponse, GetRegionResponse, GetTileResponse, InstantiateMsg,
+        ListTilesResponse, QueryMsg, WorldPoint,
+    };
+    use cosmwasm_std::testing::MockApi;
+    use cosmwasm_std::{coin, Addr, Coin, Empty, Uint128};
+    use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
+
+    pub fn contract_template() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    pub fn contract_pay() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            bitmap_pay::contract::execute,
+            bitmap_pay::contract::instantiate,
+            bitmap_pay::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    const USER: &str = "USER";
+    const ADMIN: &str = "ADMIN";
+    const RECIPIENT: &str = "RECIPIENT";
+    const NATIVE_DENOM: &str = "denom";
+
+    fn mock_app() -> App {
+        AppBuilder::new().build(|router, _, storage| {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &MockApi::default().addr_make(USER),
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    }],
+                )
+                .unwrap();
+        })
+    }
+
+    fn tile_msg(x_size: u16, y_size: u16) -> bitmap_pay::msg::InstantiateMsg {
+        bitmap_pay::msg::InstantiateMsg {
+            x_size,
+            y_size,
+            z_values: None,
+            recipient: MockApi::default().addr_make(RECIPIENT).to_string(),
+            supply_base_fee: 100,
+            supply_fee_factor: 10,
+            update_base_fee: 100,
+            update_fee_factor: 10,
+            fee_factor_scale: 100,
+            fee_denom: NATIVE_DENOM.to_string(),
+            max_update_multiplier: None,
+            admin: None,
+            access_policy: None,
+            chunked_init: None,
+        }
+    }
+
+    /// A world of 3x1 tiles of 2x2 points, where only the first two tiles are assigned
+    fn proper_instantiate() -> (App, CwTemplateContract, Vec<Addr>) {
+        let mut app = mock_app();
+        let pay_code_id = app.store_code(contract_pay());
+        let cw_template_id = app.store_code(contract_template());
+        let msg = InstantiateMsg {
+            tile_width: 2,
+            tile_height: 2,
+            tiles_x: 3,
+            tiles_y: 1,
+            admin: None,
+        };
+        let cw_template_contract_addr = app
+            .instantiate_contract(
+                cw_template_id,
+                Addr::unchecked(ADMIN),
+                &msg,
+                &[],
+                "test",
+                None,
+            )
+            .unwrap();
+        let cw_template_contract = CwTemplateContract(cw_template_contract_addr);
+        let mut tiles = vec![];
+        for tile_x in 0..2 {
+            let tile = app
+                .instantiate_contract(
+                    pay_code_id,
+                    Addr::unchecked(ADMIN),
+                    &tile_msg(2, 2),
+                    &[],
+                    "tile",
+                    None,
+                )
+                .unwrap();
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                tile.clone(),
+                &bitmap_pay::msg::ExecuteMsg::SetTrustedRouter {
+                    canvas_id: None,
+                    router: Some(cw_template_contract.addr().to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.addr(),
+                &ExecuteMsg::SetTile {
+                    tile_x,
+                    tile_y: 0,
+                    contract: tile.to_string(),
+                    canvas_id: None,
+                },
+                &[],
+            )
+            .unwrap();
+            tiles.push(tile);
+        }
+        (app, cw_template_contract, tiles)
+    }
+
+    fn balance(app: &App, address: &Addr) -> u128 {
+        app.wrap()
+            .query_balance(address, NATIVE_DENOM)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    #[test]
+    fn test_set_tile() {
+        let (mut app, cw_template_contract, tiles) = proper_instantiate();
+        let user = MockApi::default().addr_make(USER);
+        let set_tile = |tile_x: u16, contract: &Addr| ExecuteMsg::SetTile {
+            tile_x,
+            tile_y: 0,
+            contract: contract.to_string(),
+            canvas_id: None,
+        };
+        let err = app
+            .execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &set_tile(2, &tiles[0]),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Unauthorized");
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.addr(),
+                &set_tile(3, &tiles[0]),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Tile (3, 0) is outside the world"
+        );
+        let pay_code_id = app.contract_data(&tiles[0]).unwrap().code_id;
+        let wrong_size = app
+            .instantiate_contract(
+                pay_code_id,
+                Addr::unchecked(ADMIN),
+                &tile_msg(3, 2),
+                &[],
+                "tile",
+                None,
+            )
+            .unwrap();
+        let err = app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                cw_template_contract.addr(),
+                &set_tile(2, &wrong_size),
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Tile canvas is 3x2, expected 2x2"
+        );
+
+        let tile: GetTileResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetTile { x: 3, y: 1 },
+            )
+            .unwrap();
+        assert_eq!((tile.tile_x, tile.tile_y, tile.x, tile.y), (1, 0, 1, 1));
+        assert_eq!(tile.tile.unwrap().contract, tiles[1]);
+        let tile: GetTileResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetTile { x: 4, y: 0 },
+            )
+            .unwrap();
+        assert_eq!(tile.tile, None);
+
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            cw_template_contract.addr(),
+            &ExecuteMsg::RemoveTile {
+                tile_x: 0,
+                tile_y: 0,
+            },
+            &[],
+        )
+        .unwrap();
+        let list: ListTilesResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::ListTiles {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(list.tiles.len(), 1);
+        assert_eq!(list.tiles[0].tile_x, 1);
+    }
+
+    #[test]
+    fn test_set_and_cost() {
+        let (mut app, cw_template_contract, tiles) = proper_instantiate();
+        let user = MockApi::default().addr_make(USER);
+        let recipient = MockApi::default().addr_make(RECIPIENT);
+
+        // Set forwards the funds to the tile of the point
+        let cost: bitmap_pay::msg::GetCostResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &tiles[1],
+                &bitmap_pay::msg::QueryMsg::GetCost {
+                    canvas_id: None,
+                    x: 0,
+                    y: 1,
+                },
+            )
+            .unwrap();
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set {
+                x: 2,
+                y: 1,
+                z: "112233".to_string(),
+                blend: None,
+                proof: None,
+            },
+            &[coin(cost.cost, NATIVE_DENOM)],
+        )
+        .unwrap();
+        let point: bitmap_pay::msg::GetPointResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &tiles[1],
+                &bitmap_pay::msg::QueryMsg::GetPoint {
+                    canvas_id: None,
+                    x: 0,
+                    y: 1,
+                },
+            )
+            .unwrap();
+        assert_eq!(point.point, "112233");
+        assert_eq!(balance(&app, &recipient), cost.cost);
+        // The tile records the user, not the router, as the painter
+        let top: bitmap_pay::msg::GetTopPaintersResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &tiles[1],
+                &bitmap_pay::msg::QueryMsg::GetTopPainters {
+                    canvas_id: None,
+                    by: bitmap_pay::msg::PainterRanking::PixelsHeld,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(top.painters.len(), 1);
+        assert_eq!(top.painters[0].painter, user);
+
+        // Repeated points and several points on one tile are priced in order
+        let coords = vec![(0, 0), (1, 0), (0, 0), (2, 1), (3, 0)];
+        let quote: GetCostResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetCost {
+                    coords: coords.clone(),
+                },
+            )
+            .unwrap();
+        assert_eq!(quote.costs.len(), coords.len());
+        assert!(quote.costs[1].cost > quote.costs[0].cost);
+        assert!(quote.costs[2].cost > quote.costs[1].cost);
+        let total = quote.totals[0].amount.u128();
+        assert_eq!(total, quote.costs.iter().map(|c| c.cost).sum::<u128>());
+
+        let points: Vec<WorldPoint> = coords
+            .iter()
+            .map(|&(x, y)| WorldPoint {
+                x,
+                y,
+                z: "aabbcc".to_string(),
+                blend: None,
+                proof: None,
+            })
+            .collect();
+        let err = app
+            .execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::SetBatch {
+                    points: points.clone(),
+                },
+                &[coin(total - 1, NATIVE_DENOM)],
+            )
+            .unwrap_err();
+        assert_eq!(err.root_cause().to_string(), "Insufficient funds");
+
+        // Every tile accepts exactly its quoted share and the excess is refunded
+        let before = balance(&app, &user);
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &ExecuteMsg::SetBatch { points },
+            &[coin(total + 500, NATIVE_DENOM)],
+        )
+        .unwrap();
+        assert_eq!(balance(&app, &user), before - total);
+        assert_eq!(balance(&app, &recipient), cost.cost + total);
+        assert_eq!(balance(&app, &cw_template_contract.addr()), 0);
+
+        // A single Set is priced the same way, and the excess is refunded too
+        let quote: GetCostResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetCost {
+                    coords: vec![(1, 1)],
+                },
+            )
+            .unwrap();
+        let single = quote.costs[0].cost;
+        let before = balance(&app, &user);
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &ExecuteMsg::Set {
+                x: 1,
+                y: 1,
+                z: "112233".to_string(),
+                blend: None,
+                proof: None,
+            },
+            &[coin(single + 500, NATIVE_DENOM)],
+        )
+        .unwrap();
+        assert_eq!(balance(&app, &user), before - single);
+        assert_eq!(balance(&app, &recipient), cost.cost + total + single);
+        assert_eq!(balance(&app, &tiles[0]), 0);
+        assert_eq!(balance(&app, &cw_template_contract.addr()), 0);
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<GetCostResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::GetCost {
+                    coords: vec![(4, 0)],
+                },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Tile (2, 0) is not assigned"));
+    }
+
+    #[test]
+    fn test_region_across_tiles() {
+        let (mut app, cw_template_contract, tiles) = proper_instantiate();
+        let user = MockApi::default().addr_make(USER);
+        for (x, y, z) in [(1, 0, "111111"), (2, 0, "222222"), (2, 1, "333333")] {
+            app.execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &ExecuteMsg::Set {
+                    x,
+                    y,
+                    z: z.to_string(),
+                    blend: None,
+                    proof: None,
+                },
+                &[coin(10_000, NATIVE_DENOM)],
+            )
+            .unwrap();
+        }
+        let region: GetRegionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                cw_template_contract.addr(),
+                &QueryMsg::GetRegion {
+                    x0: 1,
+                    y0: 0,
+                    width: 4,
+                    height: 2,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            region.z_values,
+            [
+                "111111", "222222", "000000", "000000", // row 0
+                "000000", "333333", "000000", "000000", // row 1
+            ]
+            .concat()
+        );
+        let tile_region: bitmap_pay::msg::GetRegionResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &tiles[1],
+                &bitmap_pay::msg::QueryMsg::GetRegion {
+                    canvas_id: None,
+                    x0: 0,
+                    y0: 0,
+                    width: 1,
+                    height: 2,
+                },
+            )
+            .unwrap();
+        assert_eq!(tile_region.z_values, "222222333333");
+
+        let err = app
+            .wrap()
+            .query_wasm_smart::<GetRegionResponse>(
+                cw_template_contract.addr(),
+                &QueryMsg::GetRegion {
+                    x0: 3,
+                    y0: 0,
+                    width: 4,
+                    height: 1,
+                },
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("Region is outside the world"));
+    }
+
+    #[test]
+    fn test_merkle_tile_proof() {
+        use bitmap_pay::msg::AccessPolicy;
+        use sha2::{Digest, Sha256};
+        let (mut app, cw_template_contract, tiles) = proper_instantiate();
+        let user = MockApi::default().addr_make(USER);
+        let other = MockApi::default().addr_make("other");
+
+        // A tree of two leaves: the user's proof is the other leaf
+        let leaf = |addr: &Addr| -> [u8; 32] { Sha256::digest(addr.as_bytes()).into() };
+        let (a, b) = (leaf(&user), leaf(&other));
+        let (low, high) = if a <= b { (a, b) } else { (b, a) };
+        let root = Sha256::new().chain_update(low).chain_update(high).finalize();
+        app.execute_contract(
+            Addr::unchecked(ADMIN),
+            tiles[0].clone(),
+            &bitmap_pay::msg::ExecuteMsg::SetAccessPolicy {
+                canvas_id: None,
+                policy: AccessPolicy::MerkleAllowlist {
+                    root: hex::encode(root),
+                },
+            },
+            &[],
+        )
+        .unwrap();
+        let proof = Some(vec![hex::encode(b)]);
+        let set = |proof: Option<Vec<String>>| ExecuteMsg::Set {
+            x: 0,
+            y: 0,
+            z: "112233".to_string(),
+            blend: None,
+            proof,
+        };
+
+        let err = app
+            .execute_contract(
+                user.clone(),
+                cw_template_contract.addr(),
+                &set(None),
+                &[coin(10_000, NATIVE_DENOM)],
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Sender is not eligible to paint"
+        );
+        app.execute_contract(
+            user.clone(),
+            cw_template_contract.addr(),
+            &set(proof.clone()),
+            &[coin(10_000, NATIVE_DENOM)],
+        )
+        .unwrap();
+        app.execute_contract(
+            user,
+            cw_template_contract.addr(),
+            &ExecuteMsg::SetBatch {
+                points: vec![WorldPoint {
+                    x: 1,
+                    y: 0,
+                    z: "445566".to_string(),
+                    blend: None,
+                    proof,
+                }],
+            },
+            &[coin(10_000, NATIVE_DENOM)],
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+pub mod contract;
+mod error;
+pub mod helpers;
+pub mod integration_tests;
+pub mod msg;
+pub mod state;
+pub mod tiles;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,154 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Coin};
+
+use bitmap_pay::msg::BlendMode;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Size of every tile canvas
+    pub tile_width: u16,
+    pub tile_height: u16,
+    /// Number of tile columns and rows of the world
+    pub tiles_x: u16,
+    pub tiles_y: u16,
+    /// Defaults to the instantiating address
+    pub admin: Option<String>,
+}
+
+/// A point in world coordinates
+#[cw_serde]
+pub struct WorldPoint {
+    pub x: u32,
+    pub y: u32,
+    pub z: String,
+    pub blend: Option<BlendMode>,
+    /// Merkle proof of the sender, for tiles under a MerkleAllowlist policy
+    pub proof: Option<Vec<String>>,
+}
+
+/// Points are in world coordinates, from (0, 0) to
+/// (tiles_x * tile_width - 1, tiles_y * tile_height - 1)
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// SetTile assigns a bitmap-pay canvas of exactly tile_width x tile_height
+    /// to a tile (admin only). The canvas must trust the router with SetTrustedRouter,
+    /// as the router paints it on behalf of the sender.
+    SetTile {
+        tile_x: u16,
+        tile_y: u16,
+        contract: String,
+        canvas_id: Option<u32>,
+    },
+
+    /// RemoveTile unassigns a tile (admin only)
+    RemoveTile { tile_x: u16, tile_y: u16 },
+
+    /// Set paints the world point (x, y). Its tile is sent the exact cost of the
+    /// point and the excess funds are refunded.
+    /// `proof` is passed on for tiles under a MerkleAllowlist policy.
+    Set {
+        x: u32,
+        y: u32,
+        z: String,
+        blend: Option<BlendMode>,
+        proof: Option<Vec<String>>,
+    },
+
+    /// SetBatch paints several points, possibly on different tiles. Each tile is
+    /// sent the exact cost of its points and the excess funds are refunded.
+    SetBatch { points: Vec<WorldPoint> },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// GetConfig returns the tile and world sizes
+    #[returns(ConfigResponse)]
+    GetConfig {},
+
+    /// GetTile returns the tile of the world point (x, y) and the point's local coordinates
+    #[returns(GetTileResponse)]
+    GetTile { x: u32, y: u32 },
+
+    /// ListTiles returns the assigned tiles, row by row
+    #[returns(ListTilesResponse)]
+    ListTiles {
+        start_after: Option<(u16, u16)>,
+        limit: Option<u32>,
+    },
+
+    /// GetCost returns the cost to set each point, in the same order, and the
+    /// totals per denom. Points are priced as if set in order, so repeated
+    /// points and several points on one tile cost what SetBatch will charge.
+    #[returns(GetCostResponse)]
+    GetCost { coords: Vec<(u32, u32)> },
+
+    /// GetRegion returns a rectangle of the world, row by row, across tile
+    /// boundaries. Points of unassigned tiles are "000000".
+    #[returns(GetRegionResponse)]
+    GetRegion {
+        x0: u32,
+        y0: u32,
+        width: u16,
+        height: u16,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub admin: Addr,
+    pub tile_width: u16,
+    pub tile_height: u16,
+    pub tiles_x: u16,
+    pub tiles_y: u16,
+    pub world_width: u32,
+    pub world_height: u32,
+}
+
+#[cw_serde]
+pub struct TileInfo {
+    pub tile_x: u16,
+    pub tile_y: u16,
+    pub contract: Addr,
+    pub canvas_id: Option<u32>,
+}
+
+#[cw_serde]
+pub struct GetTileResponse {
+    pub tile_x: u16,
+    pub tile_y: u16,
+    /// Coordinates of the point on the tile canvas
+    pub x: u16,
+    pub y: u16,
+    /// None if the tile is unassigned
+    pub tile: Option<TileInfo>,
+}
+
+#[cw_serde]
+pub struct ListTilesResponse {
+    pub tiles: Vec<TileInfo>,
+}
+
+#[cw_serde]
+pub struct PointCost {
+    pub x: u32,
+    pub y: u32,
+    pub cost: u128,
+    pub denom: String,
+}
+
+#[cw_serde]
+pub struct GetCostResponse {
+    pub costs: Vec<PointCost>,
+    /// Sum of the costs per denom, in denom order
+    pub totals: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct GetRegionResponse {
+    pub x0: u32,
+    pub y0: u32,
+    pub width: u16,
+    pub height: u16,
+    pub z_values: String,
+}
@@ -0,0 +1,25 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Addr;
+use cw_storage_plus::{Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub admin: Addr,
+    pub tile_width: u16,
+    pub tile_height: u16,
+    pub tiles_x: u16, // number of tile columns
+    pub tiles_y: u16, // number of tile rows
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Tile {
+    pub contract: Addr,
+    pub canvas_id: Option<u32>, // canvas of the bitmap-pay contract, None for its default canvas
+}
+
+// Tile contracts, keyed by (tile_y, tile_x) so they range row by row
+pub const TILES: Map<(u16, u16), Tile> = Map::new("tiles");
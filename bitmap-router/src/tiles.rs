@@ -0,0 +1,199 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use cosmwasm_std::{Coin, Deps, StdError, StdResult, Uint128};
+
+use bitmap_pay::msg::{
+    GetPointResponse, GetPriceLadderResponse, GetRegionResponse as TileRegionResponse,
+    QueryMsg as TileQueryMsg,
+};
+
+use crate::msg::{GetRegionResponse, PointCost};
+use crate::state::{Config, Tile, CONFIG, TILES};
+
+/// Largest SetBatch and GetCost, within the price ladder of a tile
+pub const MAX_BATCH_POINTS: usize = 100;
+/// Largest GetRegion, the same as a single tile's
+pub const MAX_REGION_POINTS: u32 = 65536;
+/// Color of the points of unassigned tiles
+pub const EMPTY_Z: &str = "000000";
+
+/// A world point resolved to its tile
+pub struct Located {
+    pub tile_x: u16,
+    pub tile_y: u16,
+    pub x: u16,
+    pub y: u16,
+}
+
+pub fn world_size(config: &Config) -> (u32, u32) {
+    (
+        config.tiles_x as u32 * config.tile_width as u32,
+        config.tiles_y as u32 * config.tile_height as u32,
+    )
+}
+
+pub fn locate(config: &Config, x: u32, y: u32) -> StdResult<Located> {
+    let (world_width, world_height) = world_size(config);
+    if x >= world_width || y >= world_height {
+        return Err(StdError::generic_err("Point is outside the world"));
+    }
+    let (tile_width, tile_height) = (config.tile_width as u32, config.tile_height as u32);
+    Ok(Located {
+        tile_x: (x / tile_width) as u16,
+        tile_y: (y / tile_height) as u16,
+        x: (x % tile_width) as u16,
+        y: (y % tile_height) as u16,
+    })
+}
+
+pub fn load(deps: Deps, tile_x: u16, tile_y: u16) -> StdResult<Tile> {
+    TILES
+        .may_load(deps.storage, (tile_y, tile_x))?
+        .ok_or_else(|| StdError::generic_err(format!("Tile ({tile_x}, {tile_y}) is not assigned")))
+}
+
+/// Prices the points as if set one after the other. A tile's supply price goes
+/// up with each point set on it for the first time and a point's update price
+/// with each earlier occurrence, so each point is read off its price ladder.
+pub fn batch_costs(deps: Deps, coords: &[(u32, u32)]) -> StdResult<Vec<PointCost>> {
+    if coords.is_empty() || coords.len() > MAX_BATCH_POINTS {
+        return Err(StdError::generic_err(format!(
+            "Batch must hold between 1 and {MAX_BATCH_POINTS} points"
+        )));
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let mut new_points: HashMap<(u16, u16), u32> = HashMap::new();
+    let mut occurrences: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut known_set: HashSet<(u32, u32)> = HashSet::new();
+    let mut costs = Vec::with_capacity(coords.len());
+    for &(x, y) in coords {
+        let point = locate(&config, x, y)?;
+        let tile = load(deps, point.tile_x, point.tile_y)?;
+        let new_before = new_points
+            .get(&(point.tile_x, point.tile_y))
+            .copied()
+            .unwrap_or(0);
+        let seen = occurrences.entry((x, y)).or_insert(0);
+        let ladder: GetPriceLadderResponse = deps.querier.query_wasm_smart(
+            &tile.contract,
+            &TileQueryMsg::GetPriceLadder {
+                canvas_id: tile.canvas_id,
+                x: point.x,
+                y: point.y,
+                steps: new_before.max(*seen) + 1,
+            },
+        )?;
+        let cost = ladder.supply_prices[new_before as usize]
+            .saturating_add(ladder.update_prices[*seen as usize]);
+        if *seen == 0 {
+            let current: GetPointResponse = deps.querier.query_wasm_smart(
+                &tile.contract,
+                &TileQueryMsg::GetPoint {
+                    canvas_id: tile.canvas_id,
+                    x: point.x,
+                    y: point.y,
+                },
+            )?;
+            if current.is_set {
+                known_set.insert((x, y));
+            }
+        }
+        if !known_set.contains(&(x, y)) {
+            known_set.insert((x, y));
+            *new_points.entry((point.tile_x, point.tile_y)).or_insert(0) += 1;
+        }
+        *seen += 1;
+        costs.push(PointCost {
+            x,
+            y,
+            cost,
+            denom: ladder.denom,
+        });
+    }
+    Ok(costs)
+}
+
+/// Sums the costs per denom, in denom order
+pub fn totals(costs: &[PointCost]) -> Vec<Coin> {
+    let mut totals: BTreeMap<&str, u128> = BTreeMap::new();
+    for cost in costs {
+        let total = totals.entry(&cost.denom).or_insert(0);
+        *total = total.saturating_add(cost.cost);
+    }
+    totals
+        .into_iter()
+        .map(|(denom, amount)| Coin {
+            denom: denom.to_string(),
+            amount: Uint128::new(amount),
+        })
+        .collect()
+}
+
+/// Stitches a world region from the tile regions it overlaps
+pub fn get_region(
+    deps: Deps,
+    x0: u32,
+    y0: u32,
+    width: u16,
+    height: u16,
+) -> StdResult<GetRegionResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (world_width, world_height) = world_size(&config);
+    if width == 0
+        || height == 0
+        || x0.saturating_add(width as u32) > world_width
+        || y0.saturating_add(height as u32) > world_height
+    {
+        return Err(StdError::generic_err("Region is outside the world"));
+    }
+    if (width as u32) * (height as u32) > MAX_REGION_POINTS {
+        return Err(StdError::generic_err(format!(
+            "Region is larger than {MAX_REGION_POINTS} points"
+        )));
+    }
+    let (tile_width, tile_height) = (config.tile_width as u32, config.tile_height as u32);
+    let row_len = width as usize * EMPTY_Z.len();
+    let mut rows = vec![EMPTY_Z.repeat(width as usize); height as usize];
+    let (x1, y1) = (x0 + width as u32, y0 + height as u32);
+    for tile_y in y0 / tile_height..y1.div_ceil(tile_height) {
+        for tile_x in x0 / tile_width..x1.div_ceil(tile_width) {
+            let Some(tile) = TILES.may_load(deps.storage, (tile_y as u16, tile_x as u16))? else {
+                continue;
+            };
+            // Overlap of the region and the tile, in world coordinates
+            let left = x0.max(tile_x * tile_width);
+            let right = x1.min((tile_x + 1) * tile_width);
+            let top = y0.max(tile_y * tile_height);
+            let bottom = y1.min((tile_y + 1) * tile_height);
+            let part: TileRegionResponse = deps.querier.query_wasm_smart(
+                &tile.contract,
+                &TileQueryMsg::GetRegion {
+                    canvas_id: tile.canvas_id,
+                    x0: (left - tile_x * tile_width) as u16,
+                    y0: (top - tile_y * tile_height) as u16,
+                    width: (right - left) as u16,
+                    height: (bottom - top) as u16,
+                },
+            )?;
+            let part_row_len = (right - left) as usize * EMPTY_Z.len();
+            let offset = (left - x0) as usize * EMPTY_Z.len();
+            for (i, part_row) in part.z_values.as_bytes().chunks(part_row_len).enumerate() {
+                let row = &mut rows[(top - y0) as usize + i];
+                let part_row = std::str::from_utf8(part_row)
+                    .map_err(|_| StdError::generic_err("Invalid tile region"))?;
+                row.replace_range(offset..offset + part_row_len, part_row);
+            }
+        }
+    }
+    let mut z_values = String::with_capacity(row_len * height as usize);
+    for row in rows {
+        z_values.push_str(&row);
+    }
+    Ok(GetRegionResponse {
+        x0,
+        y0,
+        width,
+        height,
+        z_values,
+    })
+}
@@ -0,0 +1,174 @@
+use bitmap_pay::grid;
+use bitmap_pay::msg::GetGridResponse;
+
+use crate::dump;
+
+/// A full grid rebuilt from one or more GetGrid pages
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas {
+    pub x_size: u16,
+    pub y_size: u16,
+    pub z_values: String,
+}
+
+/// A point whose value differs between two canvases
+#[derive(Debug, PartialEq)]
+pub struct PointDiff {
+    pub x: u16,
+    pub y: u16,
+    pub before: String,
+    pub after: String,
+}
+
+impl Canvas {
+    /// Parses a dump of one or more GetGrid pages
+    pub fn from_json(json: &str) -> Result<Canvas, String> {
+        Canvas::from_pages(&dump::pages::<GetGridResponse>(json, "GetGridResponse")?)
+    }
+
+    /// Places each page at its offset. Every point of the grid must be covered.
+    pub fn from_pages(pages: &[GetGridResponse]) -> Result<Canvas, String> {
+        let first = pages.first().ok_or("No GetGrid pages")?;
+        let (x_size, y_size) = (first.x_size, first.y_size);
+        let grid_len = grid::grid_len(x_size, y_size) as usize;
+        let mut points: Vec<Option<&str>> = vec![None; grid_len];
+        for page in pages {
+            if (page.x_size, page.y_size) != (x_size, y_size) {
+                return Err("Pages come from grids of different sizes".to_string());
+            }
+            let len = page.z_values.len() / grid::Z_LEN;
+            if page.z_values.len() != len * grid::Z_LEN || !grid::is_hex(&page.z_values) {
+                return Err(format!("Invalid z_values in page at offset {}", page.offset));
+            }
+            if page.offset as usize + len > grid_len {
+                return Err(format!("Page at offset {} runs past the grid", page.offset));
+            }
+            for i in 0..len {
+                points[page.offset as usize + i] = Some(&page.z_values[i * grid::Z_LEN..(i + 1) * grid::Z_LEN]);
+            }
+        }
+        let mut z_values = String::with_capacity(grid_len * grid::Z_LEN);
+        for (idx, point) in points.into_iter().enumerate() {
+            match point {
+                Some(z) => z_values.push_str(z),
+                None => return Err(format!("Dump is missing point {}, fetch every page", idx)),
+            }
+        }
+        Ok(Canvas { x_size, y_size, z_values })
+    }
+
+    pub fn point(&self, x: u16, y: u16) -> Option<&str> {
+        let idx = grid::point_index(self.x_size, self.y_size, x, y)? as usize;
+        Some(&self.z_values[idx * grid::Z_LEN..(idx + 1) * grid::Z_LEN])
+    }
+
+    /// Returns the rectangle starting at (x0, y0) as a canvas of its own
+    pub fn crop(&self, x0: u16, y0: u16, width: u16, height: u16) -> Result<Canvas, String> {
+        if x0 as u32 + width as u32 > self.x_size as u32
+            || y0 as u32 + height as u32 > self.y_size as u32
+        {
+            return Err("Index out of bounds".to_string());
+        }
+        let mut z_values = String::with_capacity((width as usize) * (height as usize) * grid::Z_LEN);
+        for y in y0..y0 + height {
+            let start = grid::point_index(self.x_size, self.y_size, x0, y).unwrap() as usize;
+            z_values.push_str(&self.z_values[start * grid::Z_LEN..(start + width as usize) * grid::Z_LEN]);
+        }
+        Ok(Canvas {
+            x_size: width,
+            y_size: height,
+            z_values,
+        })
+    }
+
+    /// Lists the points that changed from self to other, row by row
+    pub fn diff(&self, other: &Canvas) -> Result<Vec<PointDiff>, String> {
+        if (self.x_size, self.y_size) != (other.x_size, other.y_size) {
+            return Err("Canvases have different sizes".to_string());
+        }
+        let mut diffs = vec![];
+        for y in 0..self.y_size {
+            for x in 0..self.x_size {
+                let (before, after) = (self.point(x, y).unwrap(), other.point(x, y).unwrap());
+                if !before.eq_ignore_ascii_case(after) {
+                    diffs.push(PointDiff {
+                        x,
+                        y,
+                        before: before.to_string(),
+                        after: after.to_string(),
+                    });
+                }
+            }
+        }
+        Ok(diffs)
+    }
+
+    /// Keeps the changed points of a diff and greys out the rest, so changes
+    /// stand out when rendered
+    pub fn highlight(&self, diffs: &[PointDiff]) -> Canvas {
+        let mut z_values = "3f3f3f".repeat(grid::grid_len(self.x_size, self.y_size) as usize);
+        for diff in diffs {
+            let idx = grid::point_index(self.x_size, self.y_size, diff.x, diff.y).unwrap() as usize;
+            z_values.replace_range(idx * grid::Z_LEN..(idx + 1) * grid::Z_LEN, &diff.after);
+        }
+        Canvas {
+            x_size: self.x_size,
+            y_size: self.y_size,
+            z_values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(offset: u32, z_values: &str) -> GetGridResponse {
+        GetGridResponse {
+            x_size: 3,
+            y_size: 2,
+            offset,
+            z_values: z_values.to_string(),
+            next_start_after: None,
+        }
+    }
+
+    #[test]
+    fn test_from_json() {
+        let json = r#"{"data":{"x_size":3,"y_size":2,"offset":0,"z_values":"aabbcc112233000000000000000000ffffff","next_start_after":null}}"#;
+        let canvas = Canvas::from_json(json).unwrap();
+        assert_eq!(canvas.point(0, 0), Some("aabbcc"));
+        assert_eq!(canvas.point(2, 1), Some("ffffff"));
+        assert_eq!(canvas.point(3, 0), None);
+
+        let json = r#"[{"x_size":3,"y_size":2,"offset":0,"z_values":"aabbcc112233000000","next_start_after":null},
+            {"x_size":3,"y_size":2,"offset":3,"z_values":"000000000000ffffff","next_start_after":null}]"#;
+        assert_eq!(Canvas::from_json(json).unwrap(), canvas);
+
+        let err = Canvas::from_pages(&[page(0, "aabbcc112233000000")]).unwrap_err();
+        assert_eq!(err, "Dump is missing point 3, fetch every page");
+        Canvas::from_pages(&[page(4, "aabbcc112233000000")]).unwrap_err();
+    }
+
+    #[test]
+    fn test_crop_and_diff() {
+        let before = Canvas::from_pages(&[page(0, "aabbcc112233000000000000000000ffffff")]).unwrap();
+        let after = Canvas::from_pages(&[page(0, "aabbcc445566000000000000000000FFFFFF")]).unwrap();
+
+        let crop = before.crop(1, 0, 2, 2).unwrap();
+        assert_eq!(crop.z_values, "112233000000000000ffffff");
+        before.crop(2, 0, 2, 1).unwrap_err();
+
+        let diffs = before.diff(&after).unwrap();
+        assert_eq!(
+            diffs,
+            vec![PointDiff {
+                x: 1,
+                y: 0,
+                before: "112233".to_string(),
+                after: "445566".to_string(),
+            }]
+        );
+        assert_eq!(before.highlight(&diffs).z_values, "3f3f3f4455663f3f3f3f3f3f3f3f3f3f3f3f");
+    }
+}
@@ -0,0 +1,38 @@
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Reads a dump from a file, or from stdin when path is missing or `-`
+pub fn read(path: Option<&str>) -> Result<String, String> {
+    match path {
+        None | Some("-") => {
+            let mut json = String::new();
+            std::io::stdin()
+                .read_to_string(&mut json)
+                .map_err(|e| format!("Cannot read stdin: {}", e))?;
+            Ok(json)
+        }
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e)),
+    }
+}
+
+/// Parses a query response, the `{"data": ...}` wrapper printed by
+/// `wasmd q wasm contract-state smart -o json`, or an array of either
+pub fn pages<T: DeserializeOwned>(json: &str, name: &str) -> Result<Vec<T>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let pages = match value {
+        Value::Array(pages) => pages,
+        page => vec![page],
+    };
+    pages
+        .into_iter()
+        .map(|page| {
+            let page = match page {
+                Value::Object(mut map) if map.contains_key("data") => map.remove("data").unwrap(),
+                page => page,
+            };
+            serde_json::from_value(page).map_err(|e| format!("Invalid {}: {}", name, e))
+        })
+        .collect()
+}
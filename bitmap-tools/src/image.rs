@@ -0,0 +1,154 @@
+use bitmap_pay::render;
+
+use crate::canvas::Canvas;
+
+/// Pixels with less alpha than this leave the grid untouched
+const ALPHA_THRESHOLD: u32 = 128;
+
+/// An RGBA image decoded from a PNG
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    pub fn decode_png(bytes: &[u8]) -> Result<Image, String> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| format!("Invalid PNG: {}", e))?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(|e| format!("Invalid PNG: {}", e))?;
+        let bytes = &buf[..info.buffer_size()];
+        let pixels = match info.color_type {
+            png::ColorType::Grayscale => bytes.iter().map(|&v| [v, v, v, 255]).collect(),
+            png::ColorType::GrayscaleAlpha => bytes.chunks_exact(2).map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            png::ColorType::Rgb => bytes.chunks_exact(3).map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::Rgba => bytes.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect(),
+            png::ColorType::Indexed => return Err("Unsupported indexed PNG".to_string()),
+        };
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Scales the image to width x height points. Each point averages the pixels
+    /// it covers, weighted by alpha; mostly transparent points are None.
+    pub fn resample(&self, width: u16, height: u16) -> Vec<Option<[u8; 3]>> {
+        let mut points = Vec::with_capacity(width as usize * height as usize);
+        for py in 0..height as u32 {
+            let (y0, y1) = span(py, height as u32, self.height);
+            for px in 0..width as u32 {
+                let (x0, x1) = span(px, width as u32, self.width);
+                let (mut sum, mut alpha) = ([0u64; 3], 0u64);
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        let pixel = self.pixels[(y * self.width + x) as usize];
+                        for (channel, value) in sum.iter_mut().zip(pixel) {
+                            *channel += value as u64 * pixel[3] as u64;
+                        }
+                        alpha += pixel[3] as u64;
+                    }
+                }
+                let count = ((x1 - x0) * (y1 - y0)) as u64;
+                if alpha < ALPHA_THRESHOLD as u64 * count {
+                    points.push(None);
+                    continue;
+                }
+                points.push(Some(sum.map(|channel| ((channel + alpha / 2) / alpha) as u8)));
+            }
+        }
+        points
+    }
+
+    /// Resamples the image to hex z values
+    pub fn quantize(&self, width: u16, height: u16) -> Vec<Option<String>> {
+        self.resample(width, height)
+            .into_iter()
+            .map(|point| point.map(hex))
+            .collect()
+    }
+}
+
+pub fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Source pixels covered by point i of n, when scaling a dimension of len pixels
+fn span(i: u32, n: u32, len: u32) -> (u32, u32) {
+    let start = (i as u64 * len as u64 / n as u64) as u32;
+    let end = ((i as u64 + 1) * len as u64 / n as u64) as u32;
+    (start.min(len - 1), end.max(start + 1).min(len))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Png,
+    Ppm,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Format, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Ok(Format::Png),
+            "ppm" => Ok(Format::Ppm),
+            _ => Err(format!("Unknown image format {}, expected png or ppm", name)),
+        }
+    }
+
+    /// Picks the format from a file extension, defaulting to PNG
+    pub fn from_path(path: &str) -> Format {
+        match path.rsplit_once('.') {
+            Some((_, ext)) => Format::parse(ext).unwrap_or(Format::Png),
+            None => Format::Png,
+        }
+    }
+}
+
+/// Renders a canvas with each point as a scale x scale square, using the
+/// same color parsing as the contracts' RenderImage query
+pub fn encode(canvas: &Canvas, scale: u32, format: Format) -> Vec<u8> {
+    let rows = render::rgb_rows(&canvas.z_values, canvas.x_size, canvas.y_size, scale);
+    let width = canvas.x_size as u32 * scale;
+    match format {
+        Format::Png => render::png(&rows, width),
+        Format::Ppm => {
+            let mut out = format!("P6\n{} {}\n255\n", width, rows.len()).into_bytes();
+            for row in rows {
+                out.extend_from_slice(&row);
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantize() {
+        // 4x2 image: a red and a blue square
+        let (red, blue, clear) = ([255, 0, 0, 255], [0, 0, 255, 255], [0, 0, 0, 0]);
+        let mut image = Image {
+            width: 4,
+            height: 2,
+            pixels: vec![red, red, blue, blue, red, red, blue, blue],
+        };
+        assert_eq!(
+            image.quantize(2, 1),
+            vec![Some("ff0000".to_string()), Some("0000ff".to_string())]
+        );
+        // Transparent pixels leave points untouched, and do not tint the others
+        image.pixels[3] = clear;
+        image.pixels[7] = clear;
+        assert_eq!(image.quantize(4, 1)[3], None);
+        assert_eq!(image.quantize(2, 2)[1], None);
+        image.pixels[3] = [255, 255, 255, 100];
+        assert_eq!(image.quantize(2, 1)[1], Some("2a2aff".to_string()));
+        // Upscaling repeats pixels
+        assert_eq!(image.quantize(8, 4).len(), 32);
+    }
+}
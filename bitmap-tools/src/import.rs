@@ -0,0 +1,151 @@
+use bitmap_pay::grid;
+use bitmap_pay::msg::ExecuteMsg;
+
+use crate::image::hex;
+
+/// Colors the imported points are reduced to
+#[derive(Debug)]
+pub enum Palette {
+    /// Evenly spaced levels per channel, 256 keeps every color
+    Levels(u16),
+    Colors(Vec<[u8; 3]>),
+}
+
+impl Palette {
+    /// Parses a number of levels, or a comma separated list of hex colors
+    pub fn parse(value: &str) -> Result<Palette, String> {
+        if let Ok(levels) = value.parse::<u16>() {
+            if !(2..=256).contains(&levels) {
+                return Err("Palette levels must be between 2 and 256".to_string());
+            }
+            return Ok(Palette::Levels(levels));
+        }
+        value
+            .split(',')
+            .map(|z| {
+                let z = z.trim().trim_start_matches('#');
+                grid::parse_color(z).ok_or(format!("Invalid palette color {}", z))
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map(Palette::Colors)
+    }
+
+    fn nearest(&self, color: [f32; 3]) -> [u8; 3] {
+        match self {
+            Palette::Levels(levels) => {
+                let step = 255.0 / (*levels - 1) as f32;
+                color.map(|channel| ((channel.clamp(0.0, 255.0) / step).round() * step).round() as u8)
+            }
+            Palette::Colors(colors) => *colors
+                .iter()
+                .min_by_key(|candidate| {
+                    candidate
+                        .iter()
+                        .zip(color)
+                        .map(|(&a, b)| {
+                            let d = a as f32 - b;
+                            (d * d) as u32
+                        })
+                        .sum::<u32>()
+                })
+                .unwrap(),
+        }
+    }
+}
+
+/// Builds the InstantiateMsg z_values for a resampled image. Transparent points
+/// take the background color. With dither, the rounding error of each point is
+/// spread to its neighbours (Floyd-Steinberg).
+pub fn z_values(
+    points: &[Option<[u8; 3]>],
+    width: u16,
+    palette: &Palette,
+    dither: bool,
+    background: [u8; 3],
+) -> String {
+    let width = width as usize;
+    let mut colors: Vec<[f32; 3]> = points
+        .iter()
+        .map(|point| point.unwrap_or(background).map(|channel| channel as f32))
+        .collect();
+    let mut z_values = String::with_capacity(points.len() * grid::Z_LEN);
+    for i in 0..colors.len() {
+        let color = palette.nearest(colors[i]);
+        z_values.push_str(&hex(color));
+        if !dither || points[i].is_none() {
+            continue;
+        }
+        let error = [0, 1, 2].map(|c| colors[i][c] - color[c] as f32);
+        let (x, last_row) = (i % width, i + width >= colors.len());
+        let mut spread = |j: usize, weight: f32| {
+            if points[j].is_some() {
+                for c in 0..3 {
+                    colors[j][c] += error[c] * weight;
+                }
+            }
+        };
+        if x + 1 < width {
+            spread(i + 1, 7.0 / 16.0);
+        }
+        if !last_row {
+            if x > 0 {
+                spread(i + width - 1, 3.0 / 16.0);
+            }
+            spread(i + width, 5.0 / 16.0);
+            if x + 1 < width {
+                spread(i + width + 1, 1.0 / 16.0);
+            }
+        }
+    }
+    z_values
+}
+
+/// Splits z_values into InitChunk messages of at most chunk_points points
+pub fn init_chunks(z_values: &str, chunk_points: u32, canvas_id: Option<u32>) -> Vec<ExecuteMsg> {
+    z_values
+        .as_bytes()
+        .chunks(chunk_points as usize * grid::Z_LEN)
+        .enumerate()
+        .map(|(i, data)| ExecuteMsg::InitChunk {
+            canvas_id,
+            offset: i as u32 * chunk_points,
+            data: String::from_utf8(data.to_vec()).unwrap(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_z_values() {
+        let grey = Some([100, 100, 100]);
+        let points = vec![grey, grey, grey, None];
+        // Without dithering every grey rounds to black
+        let palette = Palette::parse("000000,ffffff").unwrap();
+        assert_eq!(z_values(&points, 2, &palette, false, [255; 3]), "000000000000000000ffffff");
+        // Dithering carries the error over until a point turns white
+        let z = z_values(&points, 2, &palette, true, [255; 3]);
+        assert_eq!(z, "000000ffffff000000ffffff");
+
+        assert_eq!(z_values(&[Some([100, 200, 30])], 1, &Palette::Levels(2), false, [0; 3]), "00ff00");
+        assert_eq!(z_values(&[Some([100, 200, 30])], 1, &Palette::Levels(256), false, [0; 3]), "64c81e");
+        Palette::parse("1").unwrap_err();
+        Palette::parse("fff").unwrap_err();
+    }
+
+    #[test]
+    fn test_init_chunks() {
+        let msgs = init_chunks(&"aabbcc".repeat(5), 2, Some(1));
+        assert_eq!(msgs.len(), 3);
+        assert_eq!(
+            msgs[2],
+            ExecuteMsg::InitChunk {
+                canvas_id: Some(1),
+                offset: 4,
+                data: "aabbcc".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::io::Write;
+use std::process::exit;
+
+use bitmap_pay::msg::{GetChangesSinceResponse, GetParamsResponse, Region};
+use canvas::Canvas;
+use image::Format;
+
+mod canvas;
+mod dump;
+mod image;
+mod import;
+mod plan;
+mod pricing;
+mod simulate;
+
+const USAGE: &str = "Usage:
+  bitmap-tools render [INPUT] [--scale N] [--format png|ppm] [--out FILE]
+  bitmap-tools crop [INPUT] --x0 X --y0 Y --width W --height H [--scale N] [--format png|ppm] [--out FILE]
+  bitmap-tools diff BEFORE AFTER [--scale N] [--format png|ppm] [--out FILE]
+  bitmap-tools plan TARGET.png --grid GRID --params PARAMS [--changes CHANGES]
+               [--x0 X --y0 Y --width W --height H] [--contract ADDR] [--canvas ID]
+  bitmap-tools import IMAGE.png [--width W --height H] [--palette LEVELS|HEX,HEX,...]
+               [--dither] [--background HEX] [--chunk POINTS] [--canvas ID]
+  bitmap-tools simulate --width W --height H [--params PARAMS] [--supply-base-fee N]
+               [--supply-fee-factor N] [--update-base-fee N] [--update-fee-factor N]
+               [--fee-factor-scale N] [--max-update-multiplier N] [--num-set N]
+               [--model uniform|hotspot[:AREA[:SHARE]]|zipf[:EXPONENT]] [--paints N]
+               [--step N] [--threshold PRICE] [--seed N] [--summary] [--no-header]
+
+INPUT is a GetGrid JSON dump, or an array of GetGrid pages. It is read from
+stdin when missing or `-`. Images are written to stdout unless --out is given.
+diff lists the changed points and, with --out, renders them over a grey canvas.
+plan scales TARGET.png to the grid, or to a region of it, and prints the bitmap-pay
+Set messages needed to paint it with their exact cost. CHANGES is a get_changes_since
+dump from height 0, used to count past updates of each point. Dumps and messages
+refer to canvas 0 of the contract unless --canvas is given.
+import scales IMAGE.png to a grid and prints the z_values string for InstantiateMsg,
+or with --chunk, InitChunk messages of at most POINTS points each.
+simulate prices random Sets on a bitmap-pay grid with the contract's bonding curves and
+prints a CSV table every --step paints, or with --summary a single CSV row. Fee
+parameters come from a get_params dump, flags, or both.";
+
+/// Options that take no value
+const FLAGS: [&str; 3] = ["dither", "summary", "no-header"];
+
+/// Positional arguments and --name value options
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut positional = vec![];
+        let mut options = vec![];
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if FLAGS.contains(&name) => options.push((name.to_string(), String::new())),
+                Some(name) => {
+                    let value = args.next().ok_or(format!("Missing value for --{}", name))?;
+                    options.push((name.to_string(), value));
+                }
+                None => positional.push(arg),
+            }
+        }
+        Ok(Args { positional, options })
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.option(name)
+            .map(|value| value.parse().map_err(|_| format!("Invalid --{} {}", name, value)))
+            .transpose()
+    }
+
+    fn required<T: std::str::FromStr>(&self, name: &str) -> Result<T, String> {
+        self.number(name)?.ok_or(format!("Missing --{}", name))
+    }
+}
+
+fn read_canvas(path: Option<&str>) -> Result<Canvas, String> {
+    Canvas::from_json(&dump::read(path)?)
+}
+
+fn write_image(args: &Args, canvas: &Canvas) -> Result<(), String> {
+    let scale = args.number("scale")?.unwrap_or(1);
+    if scale == 0 {
+        return Err("--scale must be at least 1".to_string());
+    }
+    let out = args.option("out");
+    let format = match (args.option("format"), out) {
+        (Some(format), _) => Format::parse(format)?,
+        (None, Some(path)) => Format::from_path(path),
+        (None, None) => Format::Png,
+    };
+    let image = image::encode(canvas, scale, format);
+    match out {
+        Some(path) => std::fs::write(path, image).map_err(|e| format!("Cannot write {}: {}", path, e)),
+        None => std::io::stdout()
+            .write_all(&image)
+            .map_err(|e| format!("Cannot write stdout: {}", e)),
+    }
+}
+
+fn run(command: &str, args: Args) -> Result<(), String> {
+    match command {
+        "render" => {
+            let canvas = read_canvas(args.positional.first().map(String::as_str))?;
+            write_image(&args, &canvas)
+        }
+        "crop" => {
+            let canvas = read_canvas(args.positional.first().map(String::as_str))?;
+            let crop = canvas.crop(
+                args.required("x0")?,
+                args.required("y0")?,
+                args.required("width")?,
+                args.required("height")?,
+            )?;
+            write_image(&args, &crop)
+        }
+        "diff" => {
+            let [before, after] = args.positional.as_slice() else {
+                return Err("diff takes two dumps".to_string());
+            };
+            let (before, after) = (read_canvas(Some(before))?, read_canvas(Some(after))?);
+            let diffs = before.diff(&after)?;
+            for diff in &diffs {
+                println!("{},{} {} -> {}", diff.x, diff.y, diff.before, diff.after);
+            }
+            eprintln!("{} of {} points changed", diffs.len(), bitmap_pay::grid::grid_len(before.x_size, before.y_size));
+            if args.option("out").is_some() {
+                write_image(&args, &before.highlight(&diffs))?;
+            }
+            Ok(())
+        }
+        "plan" => {
+            let [target] = args.positional.as_slice() else {
+                return Err("plan takes one target image".to_string());
+            };
+            let image = std::fs::read(target).map_err(|e| format!("Cannot read {}: {}", target, e))?;
+            let image = image::Image::decode_png(&image)?;
+            let grid = args.option("grid").ok_or("Missing --grid")?;
+            let canvas = read_canvas(Some(grid))?;
+            let params = args.option("params").ok_or("Missing --params")?;
+            let params = dump::pages::<GetParamsResponse>(&dump::read(Some(params))?, "GetParamsResponse")?
+                .pop()
+                .ok_or("Empty --params dump")?;
+            let history = match args.option("changes") {
+                Some(changes) => plan::History::from_changes(&dump::pages::<GetChangesSinceResponse>(
+                    &dump::read(Some(changes))?,
+                    "GetChangesSinceResponse",
+                )?),
+                None => {
+                    eprintln!("No --changes dump, assuming points were never set; costs are estimates");
+                    plan::History {
+                        update_counts: Default::default(),
+                        truncated: false,
+                    }
+                }
+            };
+            if history.truncated {
+                eprintln!("Update counts may be incomplete, costs are estimates");
+            }
+            let region = Region {
+                x0: args.number("x0")?.unwrap_or(0),
+                y0: args.number("y0")?.unwrap_or(0),
+                width: args.number("width")?.unwrap_or(canvas.x_size),
+                height: args.number("height")?.unwrap_or(canvas.y_size),
+            };
+            let target = image.quantize(region.width, region.height);
+            let plan = plan::plan(&canvas, &params, &history, &region, &target)?;
+            let json = plan::to_json(&plan, &params.fee_denom, args.option("contract"), args.number("canvas")?);
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+            Ok(())
+        }
+        "import" => {
+            let [source] = args.positional.as_slice() else {
+                return Err("import takes one image".to_string());
+            };
+            let image = std::fs::read(source).map_err(|e| format!("Cannot read {}: {}", source, e))?;
+            let image = image::Image::decode_png(&image)?;
+            let width = args.number("width")?.unwrap_or(image.width.min(u16::MAX as u32) as u16);
+            let height = args.number("height")?.unwrap_or(image.height.min(u16::MAX as u32) as u16);
+            if width == 0 || height == 0 {
+                return Err("--width and --height must be at least 1".to_string());
+            }
+            let palette = import::Palette::parse(args.option("palette").unwrap_or("256"))?;
+            let background = args.option("background").unwrap_or("000000");
+            let background = bitmap_pay::grid::parse_color(background.trim_start_matches('#'))
+                .ok_or(format!("Invalid --background {}", background))?;
+            let points = image.resample(width, height);
+            let dither = args.option("dither").is_some();
+            let z_values = import::z_values(&points, width, &palette, dither, background);
+            match args.number::<u32>("chunk")? {
+                Some(0) => return Err("--chunk must be at least 1".to_string()),
+                Some(chunk) => {
+                    let msgs = import::init_chunks(&z_values, chunk, args.number("canvas")?);
+                    println!("{}", serde_json::to_string_pretty(&msgs).unwrap());
+                }
+                None => println!("{}", z_values),
+            }
+            Ok(())
+        }
+        "simulate" => {
+            let base = match args.option("params") {
+                Some(path) => dump::pages::<GetParamsResponse>(&dump::read(Some(path))?, "GetParamsResponse")?.pop(),
+                None => None,
+            };
+            let fee = |name: &str, field: fn(&GetParamsResponse) -> u128| -> Result<u128, String> {
+                args.number(name)?
+                    .or(base.as_ref().map(field))
+                    .ok_or(format!("Missing --{} or --params", name))
+            };
+            let params = GetParamsResponse {
+                supply_base_fee: fee("supply-base-fee", |p| p.supply_base_fee)?,
+                supply_fee_factor: fee("supply-fee-factor", |p| p.supply_fee_factor)?,
+                update_base_fee: fee("update-base-fee", |p| p.update_base_fee)?,
+                update_fee_factor: fee("update-fee-factor", |p| p.update_fee_factor)?,
+                fee_factor_scale: fee("fee-factor-scale", |p| p.fee_factor_scale)?,
+                fee_denom: base.as_ref().map(|p| p.fee_denom.clone()).unwrap_or_default(),
+                max_update_multiplier: args
+                    .number("max-update-multiplier")?
+                    .or(base.as_ref().and_then(|p| p.max_update_multiplier)),
+                num_set: args.number("num-set")?.or(base.as_ref().map(|p| p.num_set)).unwrap_or(0),
+            };
+            let paints = args.number("paints")?.unwrap_or(10_000u64);
+            let config = simulate::Config {
+                x_size: args.required("width")?,
+                y_size: args.required("height")?,
+                params,
+                model: simulate::Model::parse(args.option("model").unwrap_or("uniform"))?,
+                paints,
+                step: args.number("step")?.unwrap_or(paints.div_ceil(100).max(1)),
+                threshold: args.number("threshold")?,
+                seed: args.number("seed")?.unwrap_or(0),
+            };
+            let sim = simulate::run(&config)?;
+            let header = args.option("no-header").is_none();
+            if args.option("summary").is_some() {
+                if header {
+                    println!("{}", simulate::SUMMARY_HEADER);
+                }
+                println!("{}", simulate::summary_row(&config, &sim));
+            } else {
+                let table = if header { &sim.table[..] } else { sim.table.split_once('\n').unwrap().1 };
+                print!("{}", table);
+                eprintln!("Total revenue: {}", sim.total_revenue);
+                match sim.threshold_paint {
+                    Some(paint) => eprintln!("Prices exceed the threshold at paint {}", paint),
+                    None if config.threshold.is_some() => eprintln!("Prices never exceed the threshold"),
+                    None => {}
+                }
+            }
+            Ok(())
+        }
+        _ => Err(format!("Unknown command {}", command)),
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(command) = args.next().filter(|command| command != "--help" && command != "-h") else {
+        eprintln!("{}", USAGE);
+        exit(2);
+    };
+    let result = Args::parse(args).and_then(|args| run(&command, args));
+    if let Err(err) = result {
+        eprintln!("{}\n\n{}", err, USAGE);
+        exit(1);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use bitmap_pay::msg::{ExecuteMsg, GetChangesSinceResponse, GetParamsResponse, Region};
+use cosmwasm_std::Coin;
+use serde_json::{json, Value};
+
+use crate::canvas::Canvas;
+use crate::pricing;
+
+/// Number of times each point has been set, counted from a change feed
+pub struct History {
+    pub update_counts: HashMap<(u16, u16), u32>,
+    /// True if the feed was missing older changes, so counts are lower bounds
+    pub truncated: bool,
+}
+
+impl History {
+    pub fn from_changes(pages: &[GetChangesSinceResponse]) -> History {
+        let mut update_counts = HashMap::new();
+        let mut seen = std::collections::HashSet::new();
+        for page in pages {
+            for change in &page.changes {
+                if seen.insert(change.seq) {
+                    *update_counts.entry((change.x, change.y)).or_default() += 1;
+                }
+            }
+        }
+        History {
+            update_counts,
+            truncated: pages.iter().any(|page| page.truncated),
+        }
+    }
+}
+
+pub struct PlannedSet {
+    pub x: u16,
+    pub y: u16,
+    pub z: String,
+    pub cost: u128,
+}
+
+pub struct Plan {
+    pub sets: Vec<PlannedSet>,
+    pub total_cost: u128,
+}
+
+/// Lists a Set for each point of the region whose color differs from the target,
+/// with the exact cost the contract will charge for it. Points that were already
+/// set come first, since they do not raise the supply curve for the rest.
+pub fn plan(
+    canvas: &Canvas,
+    params: &GetParamsResponse,
+    history: &History,
+    region: &Region,
+    target: &[Option<String>],
+) -> Result<Plan, String> {
+    if region.x0 as u32 + region.width as u32 > canvas.x_size as u32
+        || region.y0 as u32 + region.height as u32 > canvas.y_size as u32
+    {
+        return Err("Region does not fit in the grid".to_string());
+    }
+    let mut state = pricing::fee_state(params, canvas.x_size, canvas.y_size);
+
+    let mut changed = vec![];
+    for (i, z) in target.iter().enumerate() {
+        let Some(z) = z else { continue };
+        let x = region.x0 + (i % region.width as usize) as u16;
+        let y = region.y0 + (i / region.width as usize) as u16;
+        if !canvas.point(x, y).unwrap().eq_ignore_ascii_case(z) {
+            let update_count = history.update_counts.get(&(x, y)).copied().unwrap_or_default();
+            changed.push((x, y, z.clone(), update_count));
+        }
+    }
+    changed.sort_by_key(|&(x, y, _, update_count)| (update_count == 0, y, x));
+
+    let mut sets = Vec::with_capacity(changed.len());
+    let mut total_cost = 0u128;
+    for (x, y, z, update_count) in changed {
+        let cost = pricing::paint(&mut state, update_count);
+        total_cost = total_cost.saturating_add(cost);
+        sets.push(PlannedSet { x, y, z, cost });
+    }
+    Ok(Plan { sets, total_cost })
+}
+
+/// Formats the plan as MsgExecuteContract bodies, each with the exact funds it needs
+pub fn to_json(plan: &Plan, denom: &str, contract: Option<&str>, canvas_id: Option<u32>) -> Value {
+    let messages = plan
+        .sets
+        .iter()
+        .map(|set| {
+            let msg = ExecuteMsg::Set {
+                canvas_id,
+                layer: None,
+                x: set.x,
+                y: set.y,
+                z: set.z.clone(),
+                blend: None,
+                proof: None,
+                on_behalf_of: None,
+            };
+            json!({
+                "contract": contract,
+                "msg": msg,
+                "funds": [Coin::new(set.cost, denom)],
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "points": plan.sets.len(),
+        "total_cost": Coin::new(plan.total_cost, denom),
+        "messages": messages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bitmap_pay::contract::execute::bonding_curve;
+    use bitmap_pay::msg::{ChangeInfo, GetGridResponse};
+    use cosmwasm_std::Addr;
+
+    #[test]
+    fn test_plan_cost() {
+        let canvas = Canvas::from_pages(&[GetGridResponse {
+            x_size: 2,
+            y_size: 1,
+            offset: 0,
+            z_values: "000000aabbcc".to_string(),
+            next_start_after: None,
+        }])
+        .unwrap();
+        let params = GetParamsResponse {
+            supply_base_fee: 1000,
+            supply_fee_factor: 1,
+            update_base_fee: 100,
+            update_fee_factor: 1,
+            fee_factor_scale: 1,
+            fee_denom: "ustake".to_string(),
+            max_update_multiplier: None,
+            num_set: 1,
+        };
+        let change = ChangeInfo {
+            seq: 0,
+            height: 1,
+            x: 1,
+            y: 0,
+            z: "aabbcc".to_string(),
+            painter: Addr::unchecked("painter"),
+        };
+        let history = History::from_changes(&[GetChangesSinceResponse {
+            changes: vec![change],
+            truncated: false,
+        }]);
+        let region = Region { x0: 0, y0: 0, width: 2, height: 1 };
+        let target = vec![Some("ffffff".to_string()), Some("AABBCC".to_string())];
+        let result = plan(&canvas, &params, &history, &region, &target).unwrap();
+        // Only the first point changes, and it was never set
+        assert_eq!(result.sets.len(), 1);
+        assert_eq!(result.sets[0].cost, bonding_curve(1000, 1.0, 1) + 100);
+
+        // The painted point is planned first, before it raises the supply curve
+        let target = vec![Some("ffffff".to_string()), Some("ffffff".to_string())];
+        let result = plan(&canvas, &params, &history, &region, &target).unwrap();
+        assert_eq!((result.sets[0].x, result.sets[1].x), (1, 0));
+        assert_eq!(result.sets[0].cost, bonding_curve(1000, 1.0, 1) + bonding_curve(100, 1.0, 1));
+        assert_eq!(result.sets[1].cost, bonding_curve(1000, 1.0, 1) + 100);
+        assert_eq!(result.total_cost, result.sets[0].cost + result.sets[1].cost);
+    }
+}
@@ -0,0 +1,33 @@
+use bitmap_pay::contract::execute::{supply_curve_cost, update_curve_cost};
+use bitmap_pay::msg::GetParamsResponse;
+use bitmap_pay::state::State;
+use cosmwasm_std::Addr;
+
+/// Contract state holding the fee parameters, so costs come from the contract's
+/// own curves. Only the fields read by the curves are meaningful.
+pub fn fee_state(params: &GetParamsResponse, x_size: u16, y_size: u16) -> State {
+    State {
+        x_size,
+        y_size,
+        admin: Addr::unchecked(""),
+        recipient: String::new(),
+        supply_base_fee: params.supply_base_fee,
+        supply_fee_factor: params.supply_fee_factor,
+        update_base_fee: params.update_base_fee,
+        update_fee_factor: params.update_fee_factor,
+        fee_factor_scale: params.fee_factor_scale,
+        fee_denom: params.fee_denom.clone(),
+        max_update_multiplier: params.max_update_multiplier,
+        num_set: params.num_set,
+    }
+}
+
+/// Cost of a Set on a point updated update_count times, the same sum as execute::set.
+/// Updates state.num_set when the point is set for the first time.
+pub fn paint(state: &mut State, update_count: u32) -> u128 {
+    let cost = supply_curve_cost(state).saturating_add(update_curve_cost(state, update_count));
+    if update_count == 0 {
+        state.num_set += 1;
+    }
+    cost
+}
@@ -0,0 +1,378 @@
+use std::fmt::Write;
+
+use bitmap_pay::contract::execute::{supply_curve_cost, update_curve_cost};
+use bitmap_pay::grid;
+use bitmap_pay::msg::GetParamsResponse;
+
+use crate::pricing;
+
+/// Largest grid the simulator keeps update counts for
+pub const MAX_SIM_POINTS: u32 = 1 << 24;
+
+/// How painters pick the point of each Set
+#[derive(Debug, Clone, PartialEq)]
+pub enum Model {
+    Uniform,
+    /// A centered square covering `area` of the grid receives `share` of the paints
+    Hotspot { area: f64, share: f64 },
+    /// The point of rank k is picked with probability proportional to 1 / k^exponent.
+    /// Ranks are scattered over the grid.
+    Zipf { exponent: f64 },
+}
+
+impl Model {
+    /// Parses `uniform`, `hotspot[:area[:share]]` or `zipf[:exponent]`
+    pub fn parse(value: &str) -> Result<Model, String> {
+        let mut parts = value.split(':');
+        let name = parts.next().unwrap_or_default();
+        let mut arg = |default: f64| -> Result<f64, String> {
+            match parts.next() {
+                Some(arg) => arg.parse().map_err(|_| format!("Invalid model argument {}", arg)),
+                None => Ok(default),
+            }
+        };
+        let model = match name {
+            "uniform" => Model::Uniform,
+            "hotspot" => Model::Hotspot {
+                area: arg(0.05)?,
+                share: arg(0.8)?,
+            },
+            "zipf" => Model::Zipf { exponent: arg(1.0)? },
+            _ => return Err(format!("Unknown model {}, expected uniform, hotspot or zipf", name)),
+        };
+        match model {
+            Model::Hotspot { area, share }
+                if area <= 0.0 || area > 1.0 || !(0.0..=1.0).contains(&share) =>
+            {
+                Err("Hotspot area must be in (0, 1] and share in [0, 1]".to_string())
+            }
+            Model::Zipf { exponent } if exponent <= 0.0 || exponent.is_nan() => {
+                Err("Zipf exponent must be positive".to_string())
+            }
+            model => Ok(model),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Model::Uniform => "uniform".to_string(),
+            Model::Hotspot { area, share } => format!("hotspot:{}:{}", area, share),
+            Model::Zipf { exponent } => format!("zipf:{}", exponent),
+        }
+    }
+}
+
+/// splitmix64, so runs are reproducible from their seed
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, n)
+    fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+struct Sampler {
+    x_size: u64,
+    y_size: u64,
+    model: Model,
+    // Centered hotspot rectangle, as (x0, y0, width, height)
+    hotspot: (u64, u64, u64, u64),
+    // Zipf rejection-inversion constants (Hörmann and Derflinger)
+    zipf_t: f64,
+    // Multiplier coprime with the grid length, scattering ranks over the grid
+    stride: u64,
+}
+
+impl Sampler {
+    fn new(x_size: u16, y_size: u16, model: Model) -> Sampler {
+        let (x_size, y_size) = (x_size as u64, y_size as u64);
+        let len = x_size * y_size;
+        let side = |size: u64, area: f64| ((size as f64 * area.sqrt()).round() as u64).clamp(1, size);
+        let hotspot = match model {
+            Model::Hotspot { area, .. } => {
+                let (w, h) = (side(x_size, area), side(y_size, area));
+                ((x_size - w) / 2, (y_size - h) / 2, w, h)
+            }
+            _ => (0, 0, x_size, y_size),
+        };
+        let zipf_t = match model {
+            Model::Zipf { exponent } if exponent != 1.0 => {
+                ((len as f64).powf(1.0 - exponent) - exponent) / (1.0 - exponent)
+            }
+            _ => 1.0 + (len as f64).ln(),
+        };
+        let mut stride = (len as f64 * 0.618_034) as u64 | 1;
+        while gcd(stride, len) != 1 {
+            stride += 2;
+        }
+        Sampler {
+            x_size,
+            y_size,
+            model,
+            hotspot,
+            zipf_t,
+            stride,
+        }
+    }
+
+    /// Returns the grid index of the next painted point
+    fn sample(&self, rng: &mut Rng) -> u64 {
+        let len = self.x_size * self.y_size;
+        match self.model {
+            Model::Uniform => rng.below(len),
+            Model::Hotspot { share, .. } => {
+                if rng.unit() >= share {
+                    return rng.below(len);
+                }
+                let (x0, y0, w, h) = self.hotspot;
+                (y0 + rng.below(h)) * self.x_size + x0 + rng.below(w)
+            }
+            Model::Zipf { exponent } => {
+                let rank = loop {
+                    let inv_b = self.zipf_inv_cdf(rng.unit(), exponent);
+                    let x = (inv_b + 1.0).floor();
+                    let mut ratio = x.powf(-exponent);
+                    if x > 1.0 {
+                        ratio *= inv_b.powf(exponent);
+                    }
+                    if rng.unit() < ratio {
+                        break (x as u64).clamp(1, len);
+                    }
+                };
+                ((rank - 1) as u128 * self.stride as u128 % len as u128) as u64
+            }
+        }
+    }
+
+    fn zipf_inv_cdf(&self, p: f64, exponent: f64) -> f64 {
+        let pt = p * self.zipf_t;
+        if pt <= 1.0 {
+            pt
+        } else if exponent != 1.0 {
+            (pt * (1.0 - exponent) + exponent).powf(1.0 / (1.0 - exponent))
+        } else {
+            (pt - 1.0).exp()
+        }
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+pub struct Simulation {
+    /// CSV table, one row every `step` paints
+    pub table: String,
+    pub paints: u64,
+    pub num_set: u32,
+    pub total_revenue: u128,
+    /// Price of setting a new point once all paints are done
+    pub final_new_point_price: u128,
+    /// First paint whose cost exceeded the threshold, counting from 1
+    pub threshold_paint: Option<u64>,
+}
+
+pub struct Config {
+    pub x_size: u16,
+    pub y_size: u16,
+    /// Fee parameters; num_set is the number of points already set
+    pub params: GetParamsResponse,
+    pub model: Model,
+    pub paints: u64,
+    /// Number of paints between table rows
+    pub step: u64,
+    pub threshold: Option<u128>,
+    pub seed: u64,
+}
+
+pub const TABLE_HEADER: &str = "paint,num_set,cost,new_point_price,max_update_count,revenue";
+
+/// Replays `paints` Sets chosen by the model and prices each one with the
+/// contract's curves, starting from params.num_set points already set.
+pub fn run(config: &Config) -> Result<Simulation, String> {
+    let Config {
+        x_size,
+        y_size,
+        ref params,
+        ref model,
+        paints,
+        step,
+        threshold,
+        seed,
+    } = *config;
+    if step == 0 {
+        return Err("step must be at least 1".to_string());
+    }
+    let len = grid::grid_len(x_size, y_size);
+    if len == 0 || len > MAX_SIM_POINTS {
+        return Err(format!("Grid must hold between 1 and {} points", MAX_SIM_POINTS));
+    }
+    if params.num_set > len {
+        return Err("num_set is larger than the grid".to_string());
+    }
+    if params.fee_factor_scale == 0 {
+        return Err("fee_factor_scale must not be 0".to_string());
+    }
+    let mut state = pricing::fee_state(params, x_size, y_size);
+    // Points already set are spread over the grid, each updated once
+    let mut update_counts = vec![0u32; len as usize];
+    let stride = Sampler::new(x_size, y_size, Model::Uniform).stride;
+    for rank in 0..params.num_set as u64 {
+        update_counts[(rank * stride % len as u64) as usize] = 1;
+    }
+
+    let sampler = Sampler::new(x_size, y_size, model.clone());
+    let mut rng = Rng::new(seed);
+    let mut table = format!("{}\n", TABLE_HEADER);
+    let (mut revenue, mut max_update_count, mut threshold_paint) = (0u128, 0u32, None);
+    for paint in 1..=paints {
+        let idx = sampler.sample(&mut rng) as usize;
+        let cost = pricing::paint(&mut state, update_counts[idx]);
+        update_counts[idx] = update_counts[idx].saturating_add(1);
+        max_update_count = max_update_count.max(update_counts[idx]);
+        revenue = revenue.saturating_add(cost);
+        if threshold_paint.is_none() && threshold.is_some_and(|threshold| cost > threshold) {
+            threshold_paint = Some(paint);
+        }
+        if paint % step == 0 || paint == paints {
+            let _ = writeln!(
+                table,
+                "{},{},{},{},{},{}",
+                paint,
+                state.num_set,
+                cost,
+                new_point_price(&state),
+                max_update_count,
+                revenue
+            );
+        }
+    }
+    Ok(Simulation {
+        table,
+        paints,
+        num_set: state.num_set,
+        total_revenue: revenue,
+        final_new_point_price: new_point_price(&state),
+        threshold_paint,
+    })
+}
+
+fn new_point_price(state: &bitmap_pay::state::State) -> u128 {
+    supply_curve_cost(state).saturating_add(update_curve_cost(state, 0))
+}
+
+pub const SUMMARY_HEADER: &str = "x_size,y_size,supply_base_fee,supply_fee_factor,update_base_fee,update_fee_factor,fee_factor_scale,max_update_multiplier,model,seed,paints,num_set,total_revenue,final_new_point_price,threshold_paint";
+
+/// One CSV row describing a run, so runs with different parameters can be compared
+pub fn summary_row(config: &Config, sim: &Simulation) -> String {
+    let params = &config.params;
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        config.x_size,
+        config.y_size,
+        params.supply_base_fee,
+        params.supply_fee_factor,
+        params.update_base_fee,
+        params.update_fee_factor,
+        params.fee_factor_scale,
+        params.max_update_multiplier.map(|max| max.to_string()).unwrap_or_default(),
+        config.model.name(),
+        config.seed,
+        sim.paints,
+        sim.num_set,
+        sim.total_revenue,
+        sim.final_new_point_price,
+        sim.threshold_paint.map(|paint| paint.to_string()).unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bitmap_pay::contract::execute::bonding_curve;
+
+    fn config(size: u16, model: Model, paints: u64, step: u64, threshold: Option<u128>) -> Config {
+        Config {
+            x_size: size,
+            y_size: size,
+            params: params(),
+            model,
+            paints,
+            step,
+            threshold,
+            seed: 7,
+        }
+    }
+
+    fn params() -> GetParamsResponse {
+        GetParamsResponse {
+            supply_base_fee: 100,
+            supply_fee_factor: 1,
+            update_base_fee: 10,
+            update_fee_factor: 50,
+            fee_factor_scale: 100,
+            fee_denom: "ustake".to_string(),
+            max_update_multiplier: Some(4),
+            num_set: 0,
+        }
+    }
+
+    #[test]
+    fn test_single_point_grid() {
+        // Every paint hits the same point: one new point, then updates
+        let sim = run(&config(1, Model::Uniform, 5, 2, Some(130))).unwrap();
+        let update = |count| bonding_curve(10, 0.5, count).min(40);
+        let costs: Vec<u128> = (0..5).map(|count| bonding_curve(100, 0.01, (count > 0) as usize) + update(count)).collect();
+        assert_eq!(sim.num_set, 1);
+        assert_eq!(sim.total_revenue, costs.iter().sum::<u128>());
+        assert_eq!(sim.threshold_paint, costs.iter().position(|&cost| cost > 130).map(|i| i as u64 + 1));
+        let rows: Vec<&str> = sim.table.lines().collect();
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], TABLE_HEADER);
+        assert!(rows[3].starts_with(&format!("5,1,{},", costs[4])));
+    }
+
+    #[test]
+    fn test_models() {
+        // Hotspot and Zipf paints concentrate on fewer points than uniform ones
+        let num_set = |model| run(&config(100, model, 5000, 5000, None)).unwrap().num_set;
+        let uniform = num_set(Model::Uniform);
+        let hotspot = num_set(Model::Hotspot { area: 0.01, share: 0.9 });
+        let zipf = num_set(Model::Zipf { exponent: 1.2 });
+        assert!(uniform > 3500, "{}", uniform);
+        assert!(hotspot < 1000, "{}", hotspot);
+        assert!(zipf < 2500, "{}", zipf);
+
+        // Runs are reproducible from their seed
+        let a = run(&config(100, Model::Zipf { exponent: 1.0 }, 1000, 100, None)).unwrap();
+        let b = run(&config(100, Model::Zipf { exponent: 1.0 }, 1000, 100, None)).unwrap();
+        assert_eq!(a.table, b.table);
+        assert_eq!(Model::parse("zipf").unwrap(), Model::Zipf { exponent: 1.0 });
+        assert_eq!(Model::parse("hotspot:0.1").unwrap(), Model::Hotspot { area: 0.1, share: 0.8 });
+        Model::parse("hotspot:2").unwrap_err();
+        Model::parse("pareto").unwrap_err();
+    }
+}